@@ -1,112 +1,298 @@
 use crate::{
     fs::{
-        exists, open,
-        path::{get_cache_path, get_cached_zip_path},
-        safe_create_dir, safe_remove_file, safe_write,
+        exists,
+        path::{get_cache_path, get_cached_digest_path, get_cached_zip_path, get_manifests_path},
+        read, read_string, safe_create_dir, safe_remove_dir, safe_remove_file, safe_write, FsError,
     },
     godot::asset_library::AssetBlob,
 };
 
 use crate::{info, traits::ReadSeek, warn};
 
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{Cursor, Read},
+    path::Component,
+};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use zip::ZipArchive;
 
-use super::{consts, AssetError};
+use super::{checksum, consts, AssetError, AssetInfo, FolderOverride};
+
+/// Archive paths of the files to extract, paired with their paths relative to the addons folder.
+pub type FilesToExtract = Vec<(String, String)>;
+
+/// File contents keyed by their path relative to the addons folder.
+pub type FilesToInstall = BTreeMap<String, Vec<u8>>;
 
 pub struct AssetArchive {
     pub id: String,
+    pub sha256: String,
     pub archive: ZipArchive<Box<dyn ReadSeek>>,
 }
 
 impl AssetArchive {
+    pub fn from_blob(id: &str, blob: &AssetBlob) -> Result<Self, AssetError> {
+        let cursor: Box<dyn ReadSeek> = Box::new(Cursor::new(blob.bytes.clone()));
+
+        Ok(AssetArchive {
+            id: id.to_string(),
+            sha256: blob.sha256.clone(),
+            archive: ZipArchive::new(cursor)?,
+        })
+    }
+
+    /// Returns the install folders of the asset, and the archive path of every file to extract
+    /// with its path relative to the addons folder.
     pub fn get_plugin_name_and_files_to_extract(
         &self,
-    ) -> Result<(String, Vec<String>), AssetError> {
-        let Some((plugin_name, plugin_path)) = self.get_plugin_info() else {
-            return Err(AssetError::InvalidAssetStructure(self.id.to_string()));
+        asset: &AssetInfo,
+    ) -> Result<(Vec<String>, FilesToExtract), AssetError> {
+        let plugin_folders = match &asset.source {
+            Some(source) => {
+                let source = source.trim_matches('/').trim_start_matches("./");
+                let source = if source == "." { "" } else { source };
+                // the archive root has no folder name to install into
+                if source.is_empty() && !matches!(asset.folder, Some(FolderOverride::Name(_))) {
+                    return Err(AssetError::MissingFolder(self.id.to_string()));
+                }
+                let plugin_name = source.rsplit('/').next().unwrap_or_default();
+                vec![(plugin_name.to_string(), source.to_string())]
+            }
+            None => self.get_plugin_infos(),
         };
 
-        let file_paths = self
-            .archive
-            .file_names()
-            .filter(|file_name| file_name.starts_with(&plugin_path))
-            .map(String::from)
-            .collect();
+        let plugin_folders = match (&asset.folder, plugin_folders.as_slice()) {
+            (_, []) => return Err(AssetError::InvalidAssetStructure(self.id.to_string())),
+            (None, _) => plugin_folders,
+            (Some(FolderOverride::Name(folder)), [(_, plugin_path)]) => {
+                vec![(folder.clone(), plugin_path.clone())]
+            }
+            (Some(FolderOverride::Name(_)), _) => {
+                return Err(AssetError::AmbiguousFolder(self.id.to_string()))
+            }
+            (Some(FolderOverride::Renames(renames)), _) => plugin_folders
+                .into_iter()
+                .map(|(plugin_name, plugin_path)| {
+                    let plugin_name = renames.get(&plugin_name).cloned().unwrap_or(plugin_name);
+                    (plugin_name, plugin_path)
+                })
+                .collect(),
+        };
+        for (plugin_name, _) in &plugin_folders {
+            if plugin_name.is_empty() {
+                return Err(AssetError::InvalidAssetStructure(self.id.to_string()));
+            }
+            if matches!(plugin_name.as_str(), "." | "..") || plugin_name.contains(['/', '\\']) {
+                return Err(AssetError::InvalidFolderName(plugin_name.clone()));
+            }
+        }
 
-        Ok((plugin_name, file_paths))
-    }
+        let include = build_glob_set(&asset.include)?;
+        let exclude = build_glob_set(&asset.exclude)?;
+        let is_included = |out_path: &str| {
+            (asset.include.is_empty() || include.is_match(out_path)) && !exclude.is_match(out_path)
+        };
 
-    pub fn get_plugin_info(&self) -> Option<(String, String)> {
-        self.archive.file_names().find_map(|file_name| {
-            let mut parts = file_name.split('/');
-            let mut full_path = Vec::new();
+        let mut files = Vec::new();
+        for (plugin_name, plugin_path) in &plugin_folders {
+            let files_in_folder = self
+                .archive
+                .file_names()
+                .filter(|file_name| is_below(file_name, plugin_path) && !file_name.ends_with('/'))
+                .map(|file_name| {
+                    let relative_path = file_name[plugin_path.len()..].trim_start_matches('/');
+                    (
+                        file_name.to_string(),
+                        format!("{plugin_name}/{relative_path}"),
+                    )
+                });
+            files.extend(files_in_folder);
+        }
+        if files.is_empty() {
+            return Err(AssetError::InvalidAssetStructure(self.id.to_string()));
+        }
 
-            // Check if "addons" is the first part or the second part
-            if let Some(first_part) = parts.next() {
-                full_path.push(first_part);
+        files.retain(|(_, out_path)| is_included(out_path));
+        if files.is_empty() {
+            return Err(AssetError::NothingIncluded(self.id.to_string()));
+        }
 
-                if first_part == consts::ADDONS_PART_PATTERN {
-                    if let Some(plugin_folder) = parts.next() {
-                        if !plugin_folder.is_empty() {
-                            full_path.push(plugin_folder);
-                            return Some((plugin_folder.to_string(), full_path.join("/")));
-                        }
-                    }
-                }
+        // folders left without files are not installed
+        let plugin_names = plugin_folders
+            .into_iter()
+            .map(|(plugin_name, _)| plugin_name)
+            .filter(|plugin_name| {
+                files
+                    .iter()
+                    .any(|(_, out_path)| is_below(out_path, plugin_name))
+            })
+            .collect();
+        Ok((plugin_names, files))
+    }
+
+    /// Reads the files the asset installs, keyed by their path relative to the addons folder,
+    /// along with the folders they are installed to.
+    pub fn read_files_to_install(
+        &mut self,
+        asset: &AssetInfo,
+    ) -> Result<(Vec<String>, FilesToInstall), AssetError> {
+        let (plugin_names, files_to_extract) = self.get_plugin_name_and_files_to_extract(asset)?;
+
+        let mut files = BTreeMap::new();
+        for (zip_path, out_path) in files_to_extract {
+            let mut entry = self.archive.by_name(&zip_path)?;
+            if entry.is_dir() {
+                continue;
+            }
+            // a link could point anywhere once extracted
+            if entry.is_symlink() {
+                return Err(AssetError::SymlinkEntry(zip_path));
+            }
+            // the out path is derived from the entry name, so it may not climb out of its folder
+            let is_enclosed = entry.enclosed_name().is_some_and(|path| {
+                path.components()
+                    .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+            });
+            if !is_enclosed {
+                return Err(AssetError::UnsafeEntry(zip_path));
             }
 
-            // If not found in the first part, check for "addons" in the next layer
-            if let Some(second_part) = parts.next() {
-                full_path.push(second_part);
-
-                if second_part == consts::ADDONS_PART_PATTERN {
-                    if let Some(plugin_folder) = parts.next() {
-                        if !plugin_folder.is_empty() {
-                            full_path.push(plugin_folder);
-                            return Some((plugin_folder.to_string(), full_path.join("/")));
-                        }
-                    }
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            files.insert(out_path, bytes);
+        }
+
+        Ok((plugin_names, files))
+    }
+
+    /// Finds the `addons/<name>` folders at the shallowest depth an addons folder occurs at in
+    /// the archive, returning their names and paths in the archive.
+    pub fn get_plugin_infos(&self) -> Vec<(String, String)> {
+        let plugin_folders: BTreeSet<(usize, &str, String)> = self
+            .archive
+            .file_names()
+            .filter_map(|file_name| {
+                let parts: Vec<&str> = file_name.split('/').collect();
+                // the folder name must be followed by another part, so it is not a file
+                let depth = parts
+                    .iter()
+                    .zip(parts.iter().skip(2))
+                    .position(|(part, _)| *part == consts::ADDONS_PART_PATTERN)?;
+                let plugin_folder = parts[depth + 1];
+                if plugin_folder.is_empty() {
+                    return None;
                 }
+                Some((depth, plugin_folder, parts[..=depth + 1].join("/")))
+            })
+            .collect();
+
+        let Some(&(min_depth, ..)) = plugin_folders.first() else {
+            return Vec::new();
+        };
+        let mut plugin_infos: Vec<(String, String)> = Vec::new();
+        for (depth, plugin_folder, plugin_path) in plugin_folders {
+            let is_new = !plugin_infos.iter().any(|(name, _)| name == plugin_folder);
+            if depth == min_depth && is_new {
+                plugin_infos.push((plugin_folder.to_string(), plugin_path));
             }
+        }
+        plugin_infos
+    }
+}
 
-            None
-        })
+/// Whether the archive entry `file_name` is below the folder `path`, where an empty path is
+/// the archive root.
+fn is_below(file_name: &str, path: &str) -> bool {
+    path.is_empty()
+        || file_name
+            .strip_prefix(path)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Compiles the include or exclude globs of an asset.
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet, AssetError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| AssetError::InvalidGlob {
+            pattern: pattern.clone(),
+            reason: e.kind().to_string(),
+        })?;
+        builder.add(glob);
     }
+
+    builder.build().map_err(|e| AssetError::InvalidGlob {
+        pattern: e.glob().unwrap_or_default().to_string(),
+        reason: e.kind().to_string(),
+    })
 }
 
-pub fn write_to_cache(id: &str, archive: &AssetBlob) -> Result<(), std::io::Error> {
+pub fn write_to_cache(id: &str, archive: &AssetBlob) -> Result<(), FsError> {
     ensure_cache_dir()?;
 
     let cached_path = get_cached_zip_path(id);
     safe_write(&cached_path, &archive.bytes)?;
+    safe_write(&get_cached_digest_path(id), &archive.sha256)?;
 
     Ok(())
 }
 
-pub fn get(id: &str) -> Result<AssetArchive, std::io::Error> {
+/// Removes the cached archive for `id`, if there is one.
+pub fn remove(id: &str) -> Result<(), FsError> {
+    for path in [get_cached_zip_path(id), get_cached_digest_path(id)] {
+        if exists(&path)? {
+            safe_remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the cached archive for `id`, or `None` if it has not been downloaded.
+///
+/// Fails if the archive no longer matches the digest recorded when it was cached.
+pub fn get(id: &str) -> Result<Option<AssetArchive>, AssetError> {
     ensure_cache_dir()?;
 
     let file_path = get_cached_zip_path(id);
+    if !exists(&file_path)? {
+        return Ok(None);
+    }
+
+    let bytes = read(&file_path)?;
+    let sha256 = checksum::sha256(&bytes);
+
+    let digest_path = get_cached_digest_path(id);
+    if exists(&digest_path)? {
+        let cached_sha256 = read_string(&digest_path)?;
+        checksum::verify(id, Some(cached_sha256.trim()), &sha256)?;
+    }
 
-    let file = open(&file_path)?;
-    let boxed_file: Box<dyn ReadSeek> = Box::new(file);
-    let archive = zip::read::ZipArchive::new(boxed_file)?;
+    let cursor: Box<dyn ReadSeek> = Box::new(Cursor::new(bytes));
+    let archive = zip::read::ZipArchive::new(cursor)?;
 
-    Ok(AssetArchive {
+    Ok(Some(AssetArchive {
         id: id.to_string(),
+        sha256,
         archive,
-    })
+    }))
 }
 
-pub fn clear() -> Result<(), std::io::Error> {
+pub fn clear() -> Result<(), FsError> {
     let cache_path = get_cache_path();
 
     let cache_dir = cache_path.read_dir()?;
 
     for entry in cache_dir {
         match entry {
+            // manifests describe what is installed, not what is cached
+            Ok(entry) if entry.path() == get_manifests_path() => (),
             Ok(entry) => {
-                safe_remove_file(&entry.path())?;
+                if entry.file_type()?.is_dir() {
+                    safe_remove_dir(&entry.path())?;
+                } else {
+                    safe_remove_file(&entry.path())?;
+                }
                 info!("Removed {} from cache", entry.file_name().to_string_lossy())
             }
             Err(e) => warn!("Failed when removing archive from cache: {e}"),
@@ -116,10 +302,158 @@ pub fn clear() -> Result<(), std::io::Error> {
     Ok(())
 }
 
-fn ensure_cache_dir() -> Result<(), std::io::Error> {
+fn ensure_cache_dir() -> Result<(), FsError> {
     let cache_path = get_cache_path();
     if !exists(cache_path)? {
         safe_create_dir(cache_path)?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use zip::write::SimpleFileOptions;
+
+    use crate::test_utils::{archive, blob, zip_writer, TestResult};
+
+    use super::*;
+
+    #[test]
+    fn shallowest_addons_folder_is_found_at_any_depth() -> TestResult {
+        let mut archive = archive(&[
+            ("repo-main/project/addons/tool/plugin.cfg", ""),
+            ("repo-main/project/addons/tool/addons/vendored/a.gd", ""),
+            ("repo-main/README.md", ""),
+        ])?;
+        let asset: AssetInfo = toml::from_str("title = \"Tool\"")?;
+
+        let (plugin_names, files) = archive.read_files_to_install(&asset)?;
+
+        assert_eq!(plugin_names, ["tool"]);
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            ["tool/addons/vendored/a.gd", "tool/plugin.cfg"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn every_addons_folder_is_installed() -> TestResult {
+        let mut archive = archive(&[
+            ("pack/addons/core/plugin.cfg", ""),
+            ("pack/addons/editor_helper/plugin.cfg", ""),
+            ("pack/addons/core/addons/vendored/a.gd", ""),
+        ])?;
+        let asset: AssetInfo = toml::from_str("title = \"Pack\"")?;
+        let renamed: AssetInfo = toml::from_str("title = \"Pack\"\nfolder = \"pack\"")?;
+
+        let (plugin_names, files) = archive.read_files_to_install(&asset)?;
+
+        assert_eq!(plugin_names, ["core", "editor_helper"]);
+        assert_eq!(files.len(), 3);
+        assert!(matches!(
+            archive.read_files_to_install(&renamed),
+            Err(AssetError::AmbiguousFolder(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn source_folder_is_installed_into_target() -> TestResult {
+        let mut archive = archive(&[
+            ("repo-main/src/my_plugin/plugin.cfg", ""),
+            ("repo-main/LICENSE", ""),
+        ])?;
+        let asset: AssetInfo = toml::from_str(
+            "title = \"Plugin\"\nsource = \"repo-main/src/my_plugin\"\ntarget = \"renamed\"",
+        )?;
+        let root: AssetInfo =
+            toml::from_str("title = \"Plugin\"\nsource = \".\"\ntarget = \"whole\"")?;
+        let unnamed_root: AssetInfo = toml::from_str("title = \"Plugin\"\nsource = \".\"")?;
+
+        let (plugin_names, files) = archive.read_files_to_install(&asset)?;
+        let (_, root_files) = archive.read_files_to_install(&root)?;
+
+        assert_eq!(plugin_names, ["renamed"]);
+        assert_eq!(files.keys().collect::<Vec<_>>(), ["renamed/plugin.cfg"]);
+        assert!(root_files.contains_key("whole/repo-main/LICENSE"));
+        assert!(matches!(
+            archive.read_files_to_install(&unnamed_root),
+            Err(AssetError::MissingFolder(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn files_are_filtered_by_include_and_exclude() -> TestResult {
+        let mut archive = archive(&[
+            ("addons/tool/plugin.cfg", ""),
+            ("addons/tool/tool.gd", ""),
+            ("addons/tool/demo/demo.tscn", ""),
+            ("addons/tool/docs/screenshot.png", ""),
+            ("addons/tool_demo/main.tscn", ""),
+        ])?;
+        let asset: AssetInfo = toml::from_str(
+            "title = \"Tool\"\ninclude = [\"tool/**\"]\nexclude = [\"**/demo/**\", \"*.png\"]",
+        )?;
+        let everything: AssetInfo = toml::from_str("title = \"Tool\"\nexclude = [\"**\"]")?;
+
+        let (plugin_names, files) = archive.read_files_to_install(&asset)?;
+
+        assert_eq!(plugin_names, ["tool"]);
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            ["tool/plugin.cfg", "tool/tool.gd"]
+        );
+        assert!(matches!(
+            archive.read_files_to_install(&everything),
+            Err(AssetError::NothingIncluded(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn folders_are_renamed_by_folder_override() -> TestResult {
+        let mut archive = archive(&[("addons/tool/plugin.cfg", ""), ("addons/utils/math.gd", "")])?;
+        let asset: AssetInfo =
+            toml::from_str("title = \"Tool\"\nfolder = { utils = \"tool_utils\" }")?;
+        let escaping: AssetInfo =
+            toml::from_str("title = \"Tool\"\nfolder = { tool = \"../tool\" }")?;
+
+        let (plugin_names, files) = archive.read_files_to_install(&asset)?;
+
+        assert_eq!(plugin_names, ["tool", "tool_utils"]);
+        assert!(files.contains_key("tool_utils/math.gd"));
+        assert!(matches!(
+            archive.read_files_to_install(&escaping),
+            Err(AssetError::InvalidFolderName(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn escaping_and_symlink_entries_are_rejected() -> TestResult {
+        let mut traversal = archive(&[
+            ("addons/tool/plugin.cfg", ""),
+            ("addons/tool/../../evil.gd", ""),
+        ])?;
+        let mut writer = zip_writer(&[("addons/tool/plugin.cfg", "")])?;
+        writer.add_symlink(
+            "addons/tool/secrets",
+            "/etc/passwd",
+            SimpleFileOptions::default(),
+        )?;
+        let mut symlink = AssetArchive::from_blob("1234", &blob(writer)?)?;
+        let asset: AssetInfo = toml::from_str("title = \"Tool\"")?;
+
+        assert!(matches!(
+            traversal.read_files_to_install(&asset),
+            Err(AssetError::UnsafeEntry(_))
+        ));
+        assert!(matches!(
+            symlink.read_files_to_install(&asset),
+            Err(AssetError::SymlinkEntry(_))
+        ));
+        Ok(())
+    }
+}
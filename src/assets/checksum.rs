@@ -0,0 +1,39 @@
+use sha2::{Digest, Sha256};
+
+use super::AssetError;
+
+/// Returns the lowercase hex encoded SHA-256 digest of `bytes`.
+pub fn sha256(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Checks `actual` against the `expected` digest, if one is known.
+pub fn verify(id: &str, expected: Option<&str>, actual: &str) -> Result<(), AssetError> {
+    match expected {
+        Some(expected) if !expected.eq_ignore_ascii_case(actual) => {
+            Err(AssetError::ChecksumMismatch {
+                id: id.to_string(),
+                expected: expected.to_string(),
+                actual: actual.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_rejects_mismatching_digest() {
+        let actual = sha256(b"godam");
+
+        assert!(verify("1234", None, &actual).is_ok());
+        assert!(verify("1234", Some(&actual.to_uppercase()), &actual).is_ok());
+        assert!(matches!(
+            verify("1234", Some(&sha256(b"tampered")), &actual),
+            Err(AssetError::ChecksumMismatch { .. })
+        ));
+    }
+}
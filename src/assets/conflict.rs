@@ -0,0 +1,143 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use super::{manifest::InstallManifest, AssetError};
+
+/// A file that several assets would install, with the IDs of those assets.
+#[derive(Debug, PartialEq)]
+pub struct Conflict {
+    pub file: String,
+    pub ids: Vec<String>,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.file, self.ids.join(", "))
+    }
+}
+
+/// Decides which asset installs each file that several assets would install, returning the
+/// files each pending asset must leave out.
+///
+/// `pending` holds the files of the assets about to be installed, keyed by asset ID, while the
+/// files of installed assets are read from their install manifests. A conflict is resolved when
+/// every asset involved is listed in `precedence`, the first one listed installing the file.
+/// Installed assets keep their files, so a pending asset cannot win over an installed one.
+pub fn resolve(
+    pending: &BTreeMap<String, BTreeSet<String>>,
+    precedence: &[String],
+) -> Result<BTreeMap<String, BTreeSet<String>>, AssetError> {
+    let installed: BTreeMap<String, BTreeSet<String>> = InstallManifest::get_all()?
+        .into_iter()
+        .filter(|(id, _)| !pending.contains_key(id))
+        .map(|(id, manifest)| (id, manifest.files.into_keys().collect()))
+        .collect();
+
+    resolve_claims(pending, &installed, precedence)
+}
+
+fn resolve_claims(
+    pending: &BTreeMap<String, BTreeSet<String>>,
+    installed: &BTreeMap<String, BTreeSet<String>>,
+    precedence: &[String],
+) -> Result<BTreeMap<String, BTreeSet<String>>, AssetError> {
+    let mut claims: BTreeMap<&String, Vec<&String>> = BTreeMap::new();
+    for (id, files) in installed.iter().chain(pending) {
+        for file in files {
+            claims.entry(file).or_default().push(id);
+        }
+    }
+
+    let rank = |id: &String| precedence.iter().position(|ranked| ranked == id);
+
+    let mut skipped: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut conflicts = Vec::new();
+    for (file, ids) in claims.into_iter().filter(|(_, ids)| ids.len() > 1) {
+        let winner = ids
+            .iter()
+            .map(|id| rank(id).map(|rank| (rank, *id)))
+            .collect::<Option<Vec<_>>>()
+            .and_then(|ranked| ranked.into_iter().min())
+            .map(|(_, id)| id);
+
+        match winner {
+            Some(winner)
+                if pending.contains_key(winner)
+                    && ids.iter().any(|id| installed.contains_key(*id)) =>
+            {
+                conflicts.push(Conflict {
+                    file: file.clone(),
+                    ids: ids.into_iter().cloned().collect(),
+                })
+            }
+            Some(winner) => {
+                for id in ids.into_iter().filter(|id| *id != winner) {
+                    skipped.entry(id.clone()).or_default().insert(file.clone());
+                }
+            }
+            None => conflicts.push(Conflict {
+                file: file.clone(),
+                ids: ids.into_iter().cloned().collect(),
+            }),
+        }
+    }
+
+    match conflicts.is_empty() {
+        true => Ok(skipped),
+        false => Err(AssetError::Conflicts(conflicts)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(entries: &[(&str, &[&str])]) -> BTreeMap<String, BTreeSet<String>> {
+        entries
+            .iter()
+            .map(|(id, files)| {
+                let files = files.iter().map(|file| file.to_string()).collect();
+                (id.to_string(), files)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn conflicts_are_resolved_by_precedence() -> Result<(), Box<dyn std::error::Error>> {
+        let pending = files(&[
+            ("1", &["utils/math.gd", "one/plugin.cfg"]),
+            ("2", &["utils/math.gd", "two/plugin.cfg"]),
+        ]);
+        let installed = files(&[("3", &["three/plugin.cfg"])]);
+
+        let Err(AssetError::Conflicts(conflicts)) = resolve_claims(&pending, &installed, &[])
+        else {
+            panic!("expected a conflict on utils/math.gd");
+        };
+        let skipped = resolve_claims(&pending, &installed, &["2".into(), "1".into()])?;
+
+        assert_eq!(
+            conflicts,
+            [Conflict {
+                file: "utils/math.gd".into(),
+                ids: vec!["1".into(), "2".into()]
+            }]
+        );
+        assert_eq!(skipped, files(&[("1", &["utils/math.gd"])]));
+        Ok(())
+    }
+
+    #[test]
+    fn installed_assets_keep_their_files() -> Result<(), Box<dyn std::error::Error>> {
+        let pending = files(&[("1", &["utils/math.gd"])]);
+        let installed = files(&[("2", &["utils/math.gd"])]);
+
+        let skipped = resolve_claims(&pending, &installed, &["2".into(), "1".into()])?;
+
+        assert_eq!(skipped, files(&[("1", &["utils/math.gd"])]));
+        assert!(resolve_claims(&pending, &installed, &["1".into(), "2".into()]).is_err());
+        Ok(())
+    }
+}
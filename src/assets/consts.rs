@@ -1 +1,4 @@
 pub const ADDONS_PART_PATTERN: &str = "addons";
+
+/// Extensions of the text files godam can show and patch line by line.
+pub const TEXT_FILE_EXTENSIONS: [&str; 5] = ["gd", "tscn", "tres", "cfg", "gdshader"];
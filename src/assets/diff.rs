@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use diffy::{DiffOptions, Patch};
+
+use crate::{
+    fs::{self, path::get_addons_path},
+    sources,
+};
+
+use super::{
+    cache::{self, AssetArchive},
+    consts,
+    manifest::InstallManifest,
+    patch, AssetError, AssetInfo,
+};
+
+/// A text file whose installed contents differ from the original archive entry.
+pub struct FileDiff {
+    /// The path of the file relative to the addons folder.
+    pub path: String,
+    pub original: String,
+    pub installed: String,
+}
+
+impl FileDiff {
+    /// A unified diff from the original to the installed contents, with git-style file headers.
+    pub fn patch(&self) -> Patch<'_, str> {
+        DiffOptions::new()
+            .set_original_filename(format!("a/{}", self.path))
+            .set_modified_filename(format!("b/{}", self.path))
+            .create_patch(&self.original, &self.installed)
+    }
+}
+
+pub fn is_text_file(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| consts::TEXT_FILE_EXTENSIONS.contains(&extension))
+}
+
+/// Returns the archive the installed files of the asset are compared against.
+///
+/// Assets from a local path are never cached, so their files are read from the path again.
+pub async fn get_original_archive(id: &str, asset: &AssetInfo) -> Result<AssetArchive, AssetError> {
+    if !asset.is_cacheable() {
+        let blob = sources::fetch(id, asset, None).await?;
+        return AssetArchive::from_blob(id, &blob);
+    }
+
+    cache::get(id)?.ok_or_else(|| AssetError::NotCached(id.to_string()))
+}
+
+/// Returns the installed text files of the asset that differ from its archive, with the
+/// patch files in `base_patches` applied.
+pub fn diff(
+    mut asset_archive: AssetArchive,
+    asset: &AssetInfo,
+    base_patches: &[String],
+) -> Result<Vec<FileDiff>, AssetError> {
+    let (_, mut expected_files) = asset_archive.read_files_to_install(asset)?;
+    patch::apply(&mut expected_files, base_patches)?;
+    let installed_files: Vec<String> = match InstallManifest::get(&asset_archive.id)? {
+        Some(manifest) => manifest.files.into_keys().collect(),
+        None => expected_files.keys().cloned().collect(),
+    };
+
+    let mut diffs = Vec::new();
+    for file in installed_files {
+        let path = get_addons_path().join(&file);
+        let Some(original) = expected_files.get(&file) else {
+            continue;
+        };
+        if !is_text_file(&file) || !fs::exists(&path)? {
+            continue;
+        }
+
+        let installed = fs::read(&path)?;
+        if &installed == original {
+            continue;
+        }
+
+        if let (Ok(original), Ok(installed)) = (
+            String::from_utf8(original.clone()),
+            String::from_utf8(installed),
+        ) {
+            diffs.push(FileDiff {
+                path: file,
+                original,
+                installed,
+            });
+        }
+    }
+
+    Ok(diffs)
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fs::{
+    exists,
+    path::{get_manifest_path, get_manifests_path},
+    read_string, safe_create_dir, safe_remove_file, safe_write,
+};
+
+use super::AssetError;
+
+/// The files godam wrote when installing an asset, keyed by their path relative to the
+/// addons folder, with the SHA-256 of the contents that were written.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct InstallManifest {
+    /// The SHA-256 of the archive the files were installed from, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    pub files: BTreeMap<String, String>,
+}
+
+impl InstallManifest {
+    /// Returns the manifest of asset `id`, or `None` if it was installed before manifests were recorded.
+    pub fn get(id: &str) -> Result<Option<Self>, AssetError> {
+        let manifest_path = get_manifest_path(id);
+        if !exists(&manifest_path)? {
+            return Ok(None);
+        }
+
+        let string = read_string(&manifest_path)?;
+        let manifest = toml::from_str(&string)?;
+
+        Ok(Some(manifest))
+    }
+
+    /// Returns the manifests of all installed assets, keyed by asset ID.
+    pub fn get_all() -> Result<BTreeMap<String, Self>, AssetError> {
+        let manifests_path = get_manifests_path();
+        if !exists(&manifests_path)? {
+            return Ok(BTreeMap::new());
+        }
+
+        let mut manifests = BTreeMap::new();
+        for file_path in crate::fs::get_files_in_directory(&manifests_path)? {
+            let Some(id) = file_path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if let Some(manifest) = Self::get(id)? {
+                manifests.insert(id.to_string(), manifest);
+            }
+        }
+
+        Ok(manifests)
+    }
+
+    pub fn save(&self, id: &str) -> Result<(), AssetError> {
+        let manifests_path = get_manifests_path();
+        if !exists(&manifests_path)? {
+            safe_create_dir(&manifests_path)?;
+        }
+
+        let str = toml::to_string(self)?;
+        Ok(safe_write(&get_manifest_path(id), str)?)
+    }
+
+    pub fn remove(id: &str) -> Result<(), AssetError> {
+        let manifest_path = get_manifest_path(id);
+        if exists(&manifest_path)? {
+            safe_remove_file(&manifest_path)?;
+        }
+        Ok(())
+    }
+}
@@ -1,64 +1,599 @@
 pub mod cache;
+pub mod checksum;
+pub mod conflict;
 pub mod consts;
+pub mod diff;
+pub mod manifest;
+pub mod patch;
+pub mod verify;
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
 
 use cache::AssetArchive;
+use conflict::Conflict;
+use manifest::InstallManifest;
+use semver::Version;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-    config::{Config, ConfigError},
+    config::{Compatibility, Config, ConfigError},
     fs::{
         self,
-        path::{get_addons_path, get_install_folder_path, get_out_path_from_archive_path},
-        safe_remove_dir,
+        path::{get_addons_path, get_backup_path, get_install_folder_path, get_staging_path},
+        safe_remove_dir, FsError,
     },
+    sources::{AssetOrigin, SourceError},
+    warn,
 };
 
 #[derive(Error, Debug)]
 pub enum AssetError {
-    #[error("Invalid asset structure. No addons folder was identified for asset with id {0}")]
+    #[error("Invalid asset structure. No addons folder was identified for asset with id {0}, set 'source' to the plugin folder in the archive")]
     InvalidAssetStructure(String),
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error(transparent)]
+    Fs(#[from] FsError),
+    #[error(transparent)]
     Zip(#[from] zip::result::ZipError),
+    #[error("Asset {0} installs the root of its archive, set 'folder' to the folder under addons to install it into")]
+    MissingFolder(String),
+    #[error("Asset {0} ships several addon folders, set 'folder' to a table of their new names")]
+    AmbiguousFolder(String),
+    #[error("'{0}' is not a valid install folder name")]
+    InvalidFolderName(String),
+    #[error("Invalid glob '{pattern}': {reason}")]
+    InvalidGlob { pattern: String, reason: String },
+    #[error("No files of asset {0} match its 'include' and 'exclude' globs")]
+    NothingIncluded(String),
+    #[error(
+        "Several assets would install the same files, set 'folder' on one of them or list them in 'precedence':\n{}",
+        format_conflicts(.0)
+    )]
+    Conflicts(Vec<Conflict>),
+    #[error("Archive entry '{0}' points outside of its folder, refusing to extract it")]
+    UnsafeEntry(String),
+    #[error("Archive entry '{0}' is a symbolic link, refusing to extract it")]
+    SymlinkEntry(String),
     #[error("Asset {0} is not installed")]
     NotInstalled(String),
+    #[error("Asset {0} is not cached, try 'godam install'")]
+    NotCached(String),
+    #[error(transparent)]
+    Source(#[from] SourceError),
+    #[error("Patch {patch} does not apply to {file}: {reason}")]
+    PatchFailed {
+        patch: String,
+        file: String,
+        reason: String,
+    },
+    #[error("Could not parse install manifest: {0}")]
+    ManifestParse(#[from] toml::de::Error),
+    #[error("Could not write install manifest: {0}")]
+    ManifestSerialize(#[from] toml::ser::Error),
     #[error(transparent)]
     Config(#[from] ConfigError),
+    #[error("{title} was made for Godot {asset_version}, but the project uses Godot {project_version}. Use --force to install it anyway.")]
+    Incompatible {
+        title: String,
+        asset_version: String,
+        project_version: String,
+    },
+    #[error("Checksum mismatch for asset {id}: expected {expected}, found {actual}. Try 'godam clean' and install again.")]
+    ChecksumMismatch {
+        id: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+fn format_conflicts(conflicts: &[Conflict]) -> String {
+    conflicts
+        .iter()
+        .map(|conflict| format!("  {conflict}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renames the install folders of an asset, e.g. so two assets shipping `addons/utils` can coexist.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum FolderOverride {
+    /// The name of the asset's only install folder.
+    Name(String),
+    /// New names keyed by the folder names in the archive. Folders not listed keep their name.
+    Renames(BTreeMap<String, String>),
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct AssetInfo {
     pub title: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub download_url: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub version: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub version_string: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub modify_date: String,
+    /// The Godot version the release was made for, as declared on the Asset Library.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub godot_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<AssetOrigin>,
+    /// The registry the asset was installed from, if not the default one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    /// Patch files applied after extraction, relative to the project root.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patches: Vec<String>,
+    /// The folder in the archive to install, instead of the `addons/<name>` folder found in it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The folders in the addons folder to install into, instead of the names they have in the archive.
+    #[serde(default, alias = "target", skip_serializing_if = "Option::is_none")]
+    pub folder: Option<FolderOverride>,
+    /// Globs of the files to install, relative to the addons folder. Everything if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Globs of the files to leave out, relative to the addons folder.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Whether to enable the asset's editor plugin in `project.godot` once installed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub enable: bool,
+}
+
+impl AssetInfo {
+    /// Whether the asset is published on the Asset Library, rather than fetched from an [`AssetOrigin`].
+    pub fn is_from_asset_library(&self) -> bool {
+        self.origin.is_none()
+    }
+
+    /// Local paths are read fresh on every install, so they are neither cached nor locked.
+    pub fn is_cacheable(&self) -> bool {
+        !matches!(self.origin, Some(AssetOrigin::Path { .. }))
+    }
+
+    /// The location the asset archive is fetched from.
+    pub fn source_url(&self) -> &str {
+        match &self.origin {
+            None => &self.download_url,
+            Some(AssetOrigin::Git { git, .. }) => git,
+            Some(AssetOrigin::Url { url }) => url,
+            Some(AssetOrigin::Path { path }) => path,
+        }
+    }
+
+    /// The branch, tag or commit a git origin asks for, which is `HEAD` if it names none.
+    pub fn git_ref(&self) -> Option<String> {
+        let Some(AssetOrigin::Git {
+            branch, tag, rev, ..
+        }) = &self.origin
+        else {
+            return None;
+        };
+        // the checkout prefers the commit, then the branch, then the tag
+        let git_ref = match (rev, branch, tag) {
+            (Some(rev), _, _) => format!("rev={rev}"),
+            (None, Some(branch), _) => format!("branch={branch}"),
+            (None, None, Some(tag)) => format!("tag={tag}"),
+            (None, None, None) => "HEAD".to_string(),
+        };
+        Some(git_ref)
+    }
+
+    /// Returns the release fields of `latest`, keeping the settings of this entry.
+    pub fn with_release(&self, latest: AssetInfo) -> AssetInfo {
+        AssetInfo {
+            title: latest.title,
+            download_url: latest.download_url,
+            version: latest.version,
+            version_string: latest.version_string,
+            modify_date: latest.modify_date,
+            godot_version: latest.godot_version,
+            ..self.clone()
+        }
+    }
+
+    /// Whether the release can be installed into a project using `godot_version`.
+    ///
+    /// Releases made for an older minor version of the same major version are assumed to keep
+    /// working. Assets that do not declare a version are always compatible.
+    pub fn is_compatible_with(&self, godot_version: &Version) -> bool {
+        let mut parts = self.godot_version.split('.').map(str::parse::<u64>);
+        let Some(Ok(major)) = parts.next() else {
+            return true;
+        };
+        let minor = parts.next().and_then(Result::ok).unwrap_or(0);
+
+        major == godot_version.major && minor <= godot_version.minor
+    }
+
+    /// Whether `latest` is a newer release of this asset on the Asset Library.
+    pub fn is_outdated_by(&self, latest: &AssetInfo) -> bool {
+        self.version != latest.version
+    }
 }
 
-pub fn install(asset_archive: AssetArchive) -> Result<(), AssetError> {
-    let (_, zip_paths_to_extract) = asset_archive.get_plugin_name_and_files_to_extract()?;
+/// Checks that the asset was made for the project's Godot version, warning about or refusing
+/// it as configured in `godam.toml`. `force` skips the check.
+pub fn check_compatibility(
+    asset: &AssetInfo,
+    config: &Config,
+    force: bool,
+) -> Result<(), AssetError> {
+    if force || asset.is_compatible_with(&config.godot_version) {
+        return Ok(());
+    }
 
-    let mut archive = asset_archive.archive;
+    let project_version = format!(
+        "{}.{}",
+        config.godot_version.major, config.godot_version.minor
+    );
+    match config.compatibility {
+        Compatibility::Warn => {
+            warn!(
+                "{} was made for Godot {}, but the project uses Godot {project_version}",
+                asset.title, asset.godot_version
+            );
+            Ok(())
+        }
+        Compatibility::Deny => Err(AssetError::Incompatible {
+            title: asset.title.clone(),
+            asset_version: asset.godot_version.clone(),
+            project_version,
+        }),
+    }
+}
 
-    for path in zip_paths_to_extract {
-        let mut contents = archive.by_name(&path)?;
-        let Some(out_path) = get_out_path_from_archive_path(&path) else {
+/// How files that already exist in the install folders are treated when installing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Overwrite {
+    /// Files the asset installed are overwritten unless they were modified since. Other files
+    /// are kept.
+    Owned,
+    /// Modified files are overwritten too. Files added by the user are kept.
+    Modified,
+    /// The install folders are wiped first, removing files added by the user too.
+    All,
+}
+
+/// Installs the asset into the project, returning the names of its install folders.
+///
+/// The archive is extracted into a staging directory first and then moved into place,
+/// so a failure at any point leaves the project as it was before. The files written
+/// are recorded in the asset's [`InstallManifest`]. `skipped` files are left to other assets,
+/// see [`conflict::resolve`].
+///
+/// Existing files are overwritten as `overwrite` allows, see [`Overwrite`].
+pub fn install(
+    mut asset_archive: AssetArchive,
+    asset: &AssetInfo,
+    skipped: &BTreeSet<String>,
+    overwrite: Overwrite,
+) -> Result<Vec<String>, AssetError> {
+    let (plugin_names, mut files) = asset_archive.read_files_to_install(asset)?;
+    patch::apply(&mut files, &asset.patches)?;
+    files.retain(|file, _| !skipped.contains(file));
+    let id = asset_archive.id;
+    let sha256 = asset_archive.sha256;
+
+    let previous_files = InstallManifest::get(&id)?
+        .map(|manifest| manifest.files)
+        .unwrap_or_default();
+    let mut kept_files = BTreeMap::new();
+    let mut overwritten_files = Vec::new();
+    let mut existing_files = Vec::new();
+    for file in files.keys() {
+        let path = get_addons_path().join(file);
+        if !fs::exists(&path)? {
             continue;
+        }
+
+        existing_files.push(file.clone());
+        match previous_files.get(file) {
+            _ if overwrite != Overwrite::Owned => overwritten_files.push(file.clone()),
+            Some(sha256) if *sha256 == checksum::sha256(&fs::read(&path)?) => {
+                overwritten_files.push(file.clone())
+            }
+            Some(sha256) => {
+                warn!("Keeping {file}, it was modified after install. Use 'godam install --reinstall {id}' to overwrite it");
+                kept_files.insert(file.clone(), sha256.clone());
+            }
+            None => {
+                warn!("Keeping {file}, it was not installed by godam. Use 'godam install --reinstall {id}' to overwrite it");
+            }
+        }
+    }
+    files.retain(|file, _| overwritten_files.contains(file) || !existing_files.contains(file));
+
+    let staging_path = get_staging_path(&id);
+    if fs::exists(&staging_path)? {
+        safe_remove_dir(&staging_path)?;
+    }
+    fs::safe_create_dir(&staging_path)?;
+
+    // overwritten files are set aside, so they can be restored if the install fails
+    let backup_path = get_backup_path(&id);
+    let mut stashed_files = Vec::new();
+    let result = overwritten_files
+        .iter()
+        .try_for_each(|file| {
+            move_file(&get_addons_path().join(file), &backup_path.join(file))?;
+            stashed_files.push(file);
+            Ok::<(), AssetError>(())
+        })
+        .and_then(|()| extract(&files, &staging_path))
+        .and_then(|mut extracted_files| {
+            let installed_files = move_into_place(&staging_path, &plugin_names)?;
+            extracted_files.retain(|file, _| installed_files.contains(file));
+            extracted_files.append(&mut kept_files);
+            InstallManifest {
+                sha256: Some(sha256),
+                files: extracted_files,
+            }
+            .save(&id)
+        });
+
+    for file in stashed_files {
+        let backup_file_path = backup_path.join(file);
+        let restored = match &result {
+            Ok(()) => fs::safe_remove_file(&backup_file_path),
+            Err(_) => move_file(&backup_file_path, &get_addons_path().join(file)),
         };
+        if let Err(e) = restored {
+            warn!("Could not restore {file}: {e}");
+        }
+    }
+    if fs::exists(&backup_path)? {
+        remove_empty_work_dir(&backup_path)?;
+    }
+
+    if let Err(e) =
+        safe_remove_dir(&staging_path).and_then(|()| remove_empty_work_dir(&staging_path))
+    {
+        warn!(
+            "Could not remove staging directory {}: {e}",
+            staging_path.display()
+        );
+    }
+
+    result.map(|()| plugin_names)
+}
+
+/// Replaces the files installed for an asset with the contents of `asset_archive`,
+/// restoring the previous files if the install fails.
+pub fn replace(
+    asset_archive: AssetArchive,
+    asset: &AssetInfo,
+    previous_install_folders: &[String],
+    skipped: &BTreeSet<String>,
+    overwrite: Overwrite,
+) -> Result<Vec<String>, AssetError> {
+    let id = asset_archive.id.clone();
+    let backup_path = get_backup_path(&id);
+    if fs::exists(&backup_path)? {
+        safe_remove_dir(&backup_path)?;
+    }
+
+    let mut stashed_files = Vec::new();
+    let installed_files = match overwrite {
+        Overwrite::All => get_files_in_folders(previous_install_folders),
+        _ => get_installed_files(&id, previous_install_folders),
+    };
+    let stash_result = installed_files.and_then(|files| {
+        // modified files are kept unless reinstalling, install reports them
+        let files = match overwrite {
+            Overwrite::Owned => files.unmodified,
+            Overwrite::Modified | Overwrite::All => [files.unmodified, files.modified].concat(),
+        };
+        for file in files {
+            move_file(&get_addons_path().join(&file), &backup_path.join(&file))?;
+            stashed_files.push(file);
+        }
+        for install_folder in previous_install_folders {
+            let install_folder_path = get_install_folder_path(install_folder);
+            if fs::exists(&install_folder_path)? {
+                fs::safe_remove_empty_dirs(&install_folder_path)?;
+            }
+        }
+        Ok(())
+    });
+
+    let result = stash_result.and_then(|()| install(asset_archive, asset, skipped, overwrite));
+
+    if result.is_err() {
+        for file in &stashed_files {
+            if let Err(e) = move_file(&backup_path.join(file), &get_addons_path().join(file)) {
+                warn!("Could not restore {file}: {e}");
+            }
+        }
+    }
+    if fs::exists(&backup_path)? {
+        safe_remove_dir(&backup_path)?;
+        remove_empty_work_dir(&backup_path)?;
+    }
+
+    result
+}
+
+/// Removes `path` and the cache directory holding it, e.g. `staging`, once they are empty,
+/// so no empty work directories are left behind after an install.
+fn remove_empty_work_dir(path: &Path) -> Result<(), FsError> {
+    if fs::exists(path)? {
+        fs::safe_remove_empty_dirs(path)?;
+    }
+    match path.parent() {
+        Some(parent) if fs::exists(parent)? => fs::safe_remove_empty_dirs(parent),
+        _ => Ok(()),
+    }
+}
+
+/// Writes the files into the staging directory, returning the SHA-256 of every
+/// file keyed by its path relative to the addons folder.
+fn extract(
+    files: &BTreeMap<String, Vec<u8>>,
+    staging_path: &Path,
+) -> Result<BTreeMap<String, String>, AssetError> {
+    let mut extracted_files = BTreeMap::new();
+
+    for (path, bytes) in files {
+        let staged_path = staging_path.join(path);
 
         // create parent dir if not exists
-        if let Some(parent) = out_path.parent() {
-            if !parent.as_os_str().is_empty() && !crate::fs::exists(parent)? {
+        if let Some(parent) = staged_path.parent() {
+            if !crate::fs::exists(parent)? {
                 crate::fs::safe_create_dir(parent)?;
             }
         }
 
-        // create file
-        if !out_path.exists() && !out_path.to_string_lossy().ends_with("/") {
-            let mut out_file = crate::fs::create(&out_path)?;
-            crate::fs::copy(&mut contents, &mut out_file)?;
+        crate::fs::safe_write(&staged_path, bytes)?;
+
+        extracted_files.insert(path.clone(), checksum::sha256(bytes));
+    }
+    Ok(extracted_files)
+}
+
+/// Moves the staged install folders into the addons folder, returning the files that were
+/// moved, relative to the addons folder.
+///
+/// A folder that is not installed yet is moved with a single rename. Otherwise the staged
+/// files are merged in, leaving existing files untouched. Everything is undone again on failure.
+fn move_into_place(
+    staging_path: &Path,
+    plugin_names: &[String],
+) -> Result<Vec<String>, AssetError> {
+    let mut moved_folders = Vec::new();
+    let mut moved_files = Vec::new();
+    let mut installed_files = Vec::new();
+
+    let result = (|| {
+        for plugin_name in plugin_names {
+            let install_folder_path = get_install_folder_path(plugin_name);
+            let staged_folder_path = staging_path.join(plugin_name);
+            // every file of the folder was left to other assets
+            if !fs::exists(&staged_folder_path)? {
+                continue;
+            }
+            let staged_files = fs::get_files_in_directory(&staged_folder_path)?;
+            let to_addons_relative =
+                |file_path: &Path| to_manifest_path(&Path::new(plugin_name).join(file_path));
+
+            if !fs::exists(&install_folder_path)? {
+                fs::safe_rename(&staged_folder_path, &install_folder_path)?;
+                moved_folders.push(install_folder_path);
+                installed_files.extend(staged_files.iter().map(|file| to_addons_relative(file)));
+                continue;
+            }
+
+            for file_path in &staged_files {
+                let target_path = install_folder_path.join(file_path);
+                if fs::exists(&target_path)? {
+                    continue;
+                }
+
+                move_file(&staged_folder_path.join(file_path), &target_path)?;
+                moved_files.push(target_path);
+                installed_files.push(to_addons_relative(file_path));
+            }
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        for file_path in moved_files.iter().rev() {
+            if let Err(e) = fs::safe_remove_file(file_path) {
+                warn!("Could not roll back {}: {e}", file_path.display());
+            }
+        }
+        for folder_path in &moved_folders {
+            if let Err(e) = safe_remove_dir(folder_path) {
+                warn!("Could not roll back {}: {e}", folder_path.display());
+            }
+        }
+        for plugin_name in plugin_names {
+            let install_folder_path = get_install_folder_path(plugin_name);
+            if fs::exists(&install_folder_path)? {
+                fs::safe_remove_empty_dirs(&install_folder_path)?;
+            }
         }
+        return Err(e);
     }
-    Ok(())
+
+    Ok(installed_files)
+}
+
+/// The files godam installed for an asset that are still present, relative to the addons folder.
+#[derive(Default)]
+struct InstalledFiles {
+    unmodified: Vec<String>,
+    /// Files modified after install, which are kept unless reinstalling.
+    modified: Vec<String>,
+}
+
+/// Returns the installed files of an asset. Assets installed before install manifests were
+/// recorded own their whole install folders.
+fn get_installed_files(id: &str, install_folders: &[String]) -> Result<InstalledFiles, AssetError> {
+    let addons_path = get_addons_path();
+
+    let Some(manifest) = InstallManifest::get(id)? else {
+        return get_files_in_folders(install_folders);
+    };
+
+    let mut installed_files = InstalledFiles::default();
+    for (file, sha256) in manifest.files {
+        let path = addons_path.join(&file);
+        if !fs::exists(&path)? {
+            continue;
+        }
+        match checksum::sha256(&fs::read(&path)?) == sha256 {
+            true => installed_files.unmodified.push(file),
+            false => installed_files.modified.push(file),
+        }
+    }
+
+    Ok(installed_files)
+}
+
+/// Returns every file in the install folders as unmodified, whoever added it.
+fn get_files_in_folders(install_folders: &[String]) -> Result<InstalledFiles, AssetError> {
+    let mut installed_files = InstalledFiles::default();
+    for install_folder in install_folders {
+        let install_folder_path = get_install_folder_path(install_folder);
+        if !fs::exists(&install_folder_path)? {
+            continue;
+        }
+        installed_files.unmodified.extend(
+            fs::get_files_in_directory(&install_folder_path)?
+                .iter()
+                .map(|file| to_manifest_path(&Path::new(install_folder).join(file))),
+        );
+    }
+    Ok(installed_files)
+}
+
+fn move_file(from: &Path, to: &Path) -> Result<(), FsError> {
+    if let Some(parent) = to.parent() {
+        if !fs::exists(parent)? {
+            fs::safe_create_dir(parent)?;
+        }
+    }
+    fs::safe_rename(from, to)
+}
+
+/// Formats a relative path the way it is stored in an [`InstallManifest`].
+pub fn to_manifest_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
 pub fn get_install_folders_in_project() -> Result<Vec<String>, AssetError> {
@@ -68,15 +603,213 @@ pub fn get_install_folders_in_project() -> Result<Vec<String>, AssetError> {
     Ok(folders)
 }
 
+/// Records the files in the install folders of an addon installed without godam in the
+/// install manifest of asset `id`, so they are treated as installed by godam from now on.
+pub fn adopt(id: &str, install_folders: &[String]) -> Result<(), AssetError> {
+    let mut manifest = InstallManifest::default();
+    for install_folder in install_folders {
+        let install_folder_path = get_install_folder_path(install_folder);
+        for file in fs::get_files_in_directory(&install_folder_path)? {
+            let sha256 = checksum::sha256(&fs::read(&install_folder_path.join(&file))?);
+            let file = to_manifest_path(&Path::new(install_folder).join(file));
+            manifest.files.insert(file, sha256);
+        }
+    }
+
+    manifest.save(id)
+}
+
+/// Removes the files godam installed for the asset, keeping anything added or modified since.
 pub fn uninstall(id: String) -> Result<(), AssetError> {
     let config = Config::get()?;
 
-    match config.get_install_folder(&id) {
-        Some(install_folder) => {
-            let asset_path = get_install_folder_path(install_folder);
-            safe_remove_dir(&asset_path)?;
-            Ok(())
+    let Some(install_folders) = config.get_install_folders(&id) else {
+        return Err(AssetError::NotInstalled(id));
+    };
+
+    let installed_files = get_installed_files(&id, install_folders)?;
+    for file in installed_files.modified {
+        warn!("Keeping {file}, it was modified after install");
+    }
+    for file in installed_files.unmodified {
+        fs::safe_remove_file(&get_addons_path().join(file))?;
+    }
+
+    let manifests = InstallManifest::get_all()?;
+    for install_folder in install_folders {
+        let install_folder_path = get_install_folder_path(install_folder);
+        if fs::exists(&install_folder_path)? {
+            fs::safe_remove_empty_dirs(&install_folder_path)?;
         }
-        None => Err(AssetError::NotInstalled(id)),
+        if !fs::exists(&install_folder_path)? {
+            continue;
+        }
+
+        // files modified after install were already reported, other assets' files are kept silently
+        for file in fs::get_files_in_directory(&install_folder_path)? {
+            let file = to_manifest_path(&Path::new(install_folder).join(file));
+            let is_installed_by_godam = manifests
+                .values()
+                .any(|manifest| manifest.files.contains_key(&file));
+            if !is_installed_by_godam {
+                warn!("Keeping {file}, it was not installed by godam");
+            }
+        }
+    }
+
+    InstallManifest::remove(&id)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{archive, TestProject, TestResult};
+
+    use super::*;
+
+    fn read(file: &str) -> Result<String, std::io::Error> {
+        fs::read_string(&get_install_folder_path("tool").join(file))
+    }
+
+    /// Installs the first release of the asset and edits one of its files, returning the
+    /// asset and its install folders.
+    fn install_and_edit() -> TestResult<(AssetInfo, Vec<String>)> {
+        let asset: AssetInfo = toml::from_str("title = \"Tool\"")?;
+        let release = [
+            ("addons/tool/owned.gd", "1"),
+            ("addons/tool/edited.gd", "1"),
+            ("addons/tool/stale.gd", "1"),
+        ];
+        let folders = install(
+            archive(&release)?,
+            &asset,
+            &BTreeSet::new(),
+            Overwrite::Owned,
+        )?;
+        std::fs::write(get_install_folder_path("tool").join("edited.gd"), "mine")?;
+        Ok((asset, folders))
+    }
+
+    const UPDATE: [(&str, &str); 2] = [
+        ("addons/tool/owned.gd", "2"),
+        ("addons/tool/edited.gd", "2"),
+    ];
+
+    #[test]
+    fn owned_files_are_overwritten() -> TestResult {
+        let _project = TestProject::new()?;
+        let (asset, folders) = install_and_edit()?;
+
+        replace(
+            archive(&UPDATE)?,
+            &asset,
+            &folders,
+            &BTreeSet::new(),
+            Overwrite::Owned,
+        )?;
+
+        assert_eq!(read("owned.gd")?, "2");
+        assert!(read("stale.gd").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn modified_files_are_kept() -> TestResult {
+        let _project = TestProject::new()?;
+        let (asset, folders) = install_and_edit()?;
+
+        replace(
+            archive(&UPDATE)?,
+            &asset,
+            &folders,
+            &BTreeSet::new(),
+            Overwrite::Owned,
+        )?;
+
+        assert_eq!(read("edited.gd")?, "mine");
+        Ok(())
+    }
+
+    #[test]
+    fn reinstalls_restore_modified_files() -> TestResult {
+        let _project = TestProject::new()?;
+        let (asset, folders) = install_and_edit()?;
+
+        replace(
+            archive(&UPDATE)?,
+            &asset,
+            &folders,
+            &BTreeSet::new(),
+            Overwrite::Modified,
+        )?;
+
+        assert_eq!(read("owned.gd")?, "2");
+        assert_eq!(read("edited.gd")?, "2");
+        Ok(())
+    }
+
+    #[test]
+    fn forced_reinstalls_remove_added_files() -> TestResult {
+        let _project = TestProject::new()?;
+        let (asset, folders) = install_and_edit()?;
+        std::fs::write(get_install_folder_path("tool").join("added.gd"), "mine")?;
+
+        replace(
+            archive(&UPDATE)?,
+            &asset,
+            &folders,
+            &BTreeSet::new(),
+            Overwrite::All,
+        )?;
+
+        assert_eq!(read("edited.gd")?, "2");
+        assert!(read("added.gd").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn failed_updates_restore_the_previous_files() -> TestResult {
+        let _project = TestProject::new()?;
+        let (asset, folders) = install_and_edit()?;
+
+        let broken = archive(&[("README.md", "no addons folder")])?;
+        assert!(replace(
+            broken,
+            &asset,
+            &folders,
+            &BTreeSet::new(),
+            Overwrite::Modified
+        )
+        .is_err());
+
+        assert_eq!(read("owned.gd")?, "1");
+        assert_eq!(read("edited.gd")?, "mine");
+        assert_eq!(read("stale.gd")?, "1");
+        assert!(!fs::exists(get_staging_path("1234").parent().unwrap())?);
+        Ok(())
+    }
+
+    #[test]
+    fn releases_for_older_minor_versions_are_compatible() -> Result<(), toml::de::Error> {
+        let asset: AssetInfo = toml::from_str("title = \"Addon\"\ngodot_version = \"4.2\"")?;
+
+        assert!(asset.is_compatible_with(&Version::new(4, 3, 0)));
+        assert!(!asset.is_compatible_with(&Version::new(4, 1, 0)));
+        assert!(!asset.is_compatible_with(&Version::new(3, 5, 0)));
+        Ok(())
+    }
+
+    #[test]
+    fn incompatible_releases_are_denied_unless_forced() -> TestResult {
+        let asset: AssetInfo = toml::from_str("title = \"Addon\"\ngodot_version = \"3.5\"")?;
+        let config: Config = toml::from_str(
+            "godot_version = \"4.3.0\"\ncompatibility = \"deny\"\n[asset_infos]\n[install_folders]",
+        )?;
+
+        assert!(matches!(
+            check_compatibility(&asset, &config, false),
+            Err(AssetError::Incompatible { .. })
+        ));
+        assert!(check_compatibility(&asset, &config, true).is_ok());
+        Ok(())
     }
 }
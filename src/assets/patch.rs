@@ -0,0 +1,196 @@
+use std::{collections::BTreeMap, path::Path};
+
+use diffy::Patch;
+
+use crate::fs;
+
+use super::{diff::FileDiff, AssetError};
+
+/// Applies the patch files, in order, to the files about to be installed.
+///
+/// Every patch must apply cleanly, so a patch that went stale after an update fails the install.
+pub fn apply(
+    files: &mut BTreeMap<String, Vec<u8>>,
+    patch_paths: &[String],
+) -> Result<(), AssetError> {
+    for patch_path in patch_paths {
+        let text = fs::read_string(Path::new(patch_path)).map_err(|e| AssetError::PatchFailed {
+            patch: patch_path.clone(),
+            file: "-".to_string(),
+            reason: e.to_string(),
+        })?;
+        apply_patch(files, patch_path, &text)?;
+    }
+
+    Ok(())
+}
+
+fn apply_patch(
+    files: &mut BTreeMap<String, Vec<u8>>,
+    patch_path: &str,
+    text: &str,
+) -> Result<(), AssetError> {
+    let patch_failed = |file: &str, reason: String| AssetError::PatchFailed {
+        patch: patch_path.to_string(),
+        file: file.to_string(),
+        reason,
+    };
+
+    for file_patch in split(text) {
+        let patch = Patch::from_str(file_patch).map_err(|e| patch_failed("-", e.to_string()))?;
+        let Some(file) = patch.modified().map(strip_prefix) else {
+            return Err(patch_failed("-", "missing '+++' file header".to_string()));
+        };
+
+        let Some(original) = files.get(file) else {
+            return Err(patch_failed(
+                file,
+                "file is not part of the asset".to_string(),
+            ));
+        };
+        let original = std::str::from_utf8(original)
+            .map_err(|_| patch_failed(file, "file is not a text file".to_string()))?;
+
+        let patched =
+            diffy::apply(original, &patch).map_err(|e| patch_failed(file, e.to_string()))?;
+        files.insert(file.to_string(), patched.into_bytes());
+    }
+
+    Ok(())
+}
+
+/// Returns the files, relative to the addons folder, that the patch file at `patch_path` changes.
+pub fn get_patched_files(patch_path: &str) -> Result<Vec<String>, AssetError> {
+    let text = fs::read_string(Path::new(patch_path)).map_err(|e| AssetError::PatchFailed {
+        patch: patch_path.to_string(),
+        file: "-".to_string(),
+        reason: e.to_string(),
+    })?;
+    patched_files(patch_path, &text)
+}
+
+fn patched_files(patch_path: &str, text: &str) -> Result<Vec<String>, AssetError> {
+    let patch_failed = |reason: String| AssetError::PatchFailed {
+        patch: patch_path.to_string(),
+        file: "-".to_string(),
+        reason,
+    };
+
+    split(text)
+        .into_iter()
+        .map(|file_patch| {
+            let patch = Patch::from_str(file_patch).map_err(|e| patch_failed(e.to_string()))?;
+            match patch.modified() {
+                Some(file) => Ok(strip_prefix(file).to_string()),
+                None => Err(patch_failed("missing '+++' file header".to_string())),
+            }
+        })
+        .collect()
+}
+
+/// Combines the diffs into a single patch file.
+pub fn create(diffs: &[FileDiff]) -> String {
+    diffs
+        .iter()
+        .map(|file_diff| file_diff.patch().to_string())
+        .collect()
+}
+
+/// Splits a patch file into the patches of the individual files it touches.
+///
+/// Hunk line counts are followed, so removed lines starting with `--` are not
+/// mistaken for the header of the next file.
+fn split(text: &str) -> Vec<&str> {
+    let mut file_patches = Vec::new();
+    let mut start = None;
+    let mut remaining_old: usize = 0;
+    let mut remaining_new: usize = 0;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        if remaining_old > 0 || remaining_new > 0 {
+            match line.chars().next() {
+                Some('-') => remaining_old = remaining_old.saturating_sub(1),
+                Some('+') => remaining_new = remaining_new.saturating_sub(1),
+                Some('\\') => (),
+                _ => {
+                    remaining_old = remaining_old.saturating_sub(1);
+                    remaining_new = remaining_new.saturating_sub(1);
+                }
+            }
+        } else if line.starts_with("--- ") {
+            if let Some(start) = start {
+                file_patches.push(&text[start..offset]);
+            }
+            start = Some(offset);
+        } else if let Some((old, new)) = parse_hunk_header(line) {
+            remaining_old = old;
+            remaining_new = new;
+        }
+        offset += line.len();
+    }
+
+    if let Some(start) = start {
+        file_patches.push(&text[start..]);
+    }
+    file_patches
+}
+
+/// Returns the old and new line counts of a `@@ -a,b +c,d @@` hunk header.
+fn parse_hunk_header(line: &str) -> Option<(usize, usize)> {
+    let ranges = line.strip_prefix("@@ -")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(" +")?;
+    let count = |range: &str| match range.split_once(',') {
+        Some((_, count)) => count.parse().ok(),
+        None => Some(1),
+    };
+    Some((count(old)?, count(new)?))
+}
+
+fn strip_prefix(file: &str) -> &str {
+    file.strip_prefix("b/").unwrap_or(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_follows_hunks_across_files() {
+        let text = "--- a/x/a.gd\n+++ b/x/a.gd\n@@ -1,2 +1,2 @@\n--- removed\n+++ added\n kept\n--- a/x/b.gd\n+++ b/x/b.gd\n@@ -1 +1 @@\n-old\n+new\n";
+
+        let file_patches = split(text);
+
+        assert_eq!(file_patches.len(), 2);
+        assert!(file_patches[0].ends_with(" kept\n"));
+        assert!(file_patches[1].starts_with("--- a/x/b.gd\n"));
+        assert_eq!(
+            patched_files("patches/x.patch", text).unwrap(),
+            ["x/a.gd", "x/b.gd"]
+        );
+    }
+
+    #[test]
+    fn created_patches_apply_only_to_original_files() -> Result<(), Box<dyn std::error::Error>> {
+        let mut stale = BTreeMap::from([("x/a.gd".to_string(), b"extends Node\n".to_vec())]);
+        let file_diff = FileDiff {
+            path: "x/a.gd".to_string(),
+            original: "extends Node\n\nfunc a():\n\tpass\n".to_string(),
+            installed: "extends Node\n\nfunc a():\n\tprint(\"patched\")\n".to_string(),
+        };
+        let text = create(std::slice::from_ref(&file_diff));
+
+        let mut files = BTreeMap::from([(
+            file_diff.path.clone(),
+            file_diff.original.clone().into_bytes(),
+        )]);
+        apply_patch(&mut files, "patches/x.patch", &text)?;
+
+        assert_eq!(files[&file_diff.path], file_diff.installed.as_bytes());
+        assert!(matches!(
+            apply_patch(&mut stale, "patches/x.patch", &text),
+            Err(AssetError::PatchFailed { .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use std::path::Path;
+
+use crate::fs::{
+    self,
+    path::{get_addons_path, get_install_folder_path},
+};
+
+use super::{
+    cache::AssetArchive, checksum, manifest::InstallManifest, patch, to_manifest_path, AssetError,
+    AssetInfo,
+};
+
+/// How the installed files of an asset differ from its archive, as paths relative to the addons folder.
+#[derive(Default, Debug, PartialEq)]
+pub struct Drift {
+    pub modified: Vec<String>,
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+}
+
+impl Drift {
+    pub fn is_empty(&self) -> bool {
+        self.modified.is_empty() && self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+/// Compares the files godam installed for an asset against the contents of its archive,
+/// with the asset's patches applied.
+pub fn verify(
+    mut asset_archive: AssetArchive,
+    asset: &AssetInfo,
+    install_folders: &[String],
+) -> Result<Drift, AssetError> {
+    let (_, mut expected_files) = asset_archive.read_files_to_install(asset)?;
+    patch::apply(&mut expected_files, &asset.patches)?;
+    let manifests = InstallManifest::get_all()?;
+
+    // files already present when the asset was installed belong to someone else
+    let installed_files: Vec<(&String, String)> = match manifests.get(&asset_archive.id) {
+        Some(manifest) => manifest
+            .files
+            .iter()
+            .map(|(file, sha256)| match expected_files.get(file) {
+                Some(bytes) => (file, checksum::sha256(bytes)),
+                None => (file, sha256.clone()),
+            })
+            .collect(),
+        None => expected_files
+            .iter()
+            .map(|(file, bytes)| (file, checksum::sha256(bytes)))
+            .collect(),
+    };
+
+    let is_installed_by_godam = |file: &String| {
+        expected_files.contains_key(file)
+            || manifests
+                .values()
+                .any(|manifest| manifest.files.contains_key(file))
+    };
+
+    compare(installed_files, install_folders, is_installed_by_godam)
+}
+
+/// Compares the files godam installed for asset `id` against the checksums in its install
+/// manifest, for assets whose archive is not cached. Returns `None` if the asset has no manifest.
+pub fn verify_manifest(id: &str, install_folders: &[String]) -> Result<Option<Drift>, AssetError> {
+    let manifests = InstallManifest::get_all()?;
+    let Some(manifest) = manifests.get(id) else {
+        return Ok(None);
+    };
+
+    let installed_files = manifest
+        .files
+        .iter()
+        .map(|(file, sha256)| (file, sha256.clone()))
+        .collect();
+    let is_installed_by_godam = |file: &String| {
+        manifests
+            .values()
+            .any(|manifest| manifest.files.contains_key(file))
+    };
+
+    compare(installed_files, install_folders, is_installed_by_godam).map(Some)
+}
+
+fn compare(
+    installed_files: Vec<(&String, String)>,
+    install_folders: &[String],
+    is_installed_by_godam: impl Fn(&String) -> bool,
+) -> Result<Drift, AssetError> {
+    let mut drift = Drift::default();
+    for (file, expected_sha256) in installed_files {
+        let path = get_addons_path().join(file);
+        if !fs::exists(&path)? {
+            drift.missing.push(file.clone());
+        } else if checksum::sha256(&fs::read(&path)?) != expected_sha256 {
+            drift.modified.push(file.clone());
+        }
+    }
+
+    for install_folder in install_folders {
+        let install_folder_path = get_install_folder_path(install_folder);
+        if !fs::exists(&install_folder_path)? {
+            continue;
+        }
+        for file in fs::get_files_in_directory(&install_folder_path)? {
+            let file = to_manifest_path(&Path::new(install_folder).join(file));
+            if !is_installed_by_godam(&file) {
+                drift.extra.push(file);
+            }
+        }
+    }
+
+    Ok(drift)
+}
@@ -0,0 +1,189 @@
+use thiserror::Error;
+
+use crate::{
+    assets::{self, AssetError, AssetInfo},
+    config::{Config, ConfigError},
+    godot::{
+        asset_library::{self, AssetSearchResult},
+        plugin::PluginConfig,
+    },
+    info, prompt_char, warn,
+};
+
+#[derive(Error, Debug)]
+pub enum AdoptError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Asset(#[from] AssetError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Matches addon folders that godam does not manage yet to Asset Library assets, and adds
+/// them to `godam.toml` once confirmed.
+pub async fn exec(registry_name: &Option<String>) -> Result<(), AdoptError> {
+    let mut config = Config::get()?;
+    let registry = config.get_registry(registry_name.as_deref())?;
+
+    let mut install_folders = assets::get_install_folders_in_project()?;
+    install_folders.sort();
+
+    let mut adopted = 0;
+    let mut skipped = 0;
+    for install_folder in install_folders {
+        let is_managed = config
+            .install_folders
+            .values()
+            .any(|folders| folders.contains(&install_folder));
+        if is_managed || install_folder.starts_with('.') {
+            continue;
+        }
+
+        // a folder that cannot be looked up is reported and skipped, the others are still adopted
+        let plugin = match PluginConfig::get(&install_folder) {
+            Ok(plugin) => plugin,
+            Err(e) => {
+                warn!("Skipping {install_folder}: {e}");
+                skipped += 1;
+                continue;
+            }
+        };
+        let search_term = match &plugin {
+            Some(plugin) if !plugin.name.is_empty() => plugin.name.clone(),
+            _ => install_folder.replace(['_', '-'], " "),
+        };
+        let author = plugin
+            .as_ref()
+            .map(|plugin| plugin.author.as_str())
+            .unwrap_or_default();
+
+        let results =
+            match asset_library::get_assets_by_name(&registry, &search_term, &config.godot_version)
+                .await
+            {
+                Ok(results) => results,
+                Err(e) => {
+                    warn!("Skipping {install_folder}: {e}");
+                    skipped += 1;
+                    continue;
+                }
+            };
+        let Some(result) = find_match(&search_term, author, &results) else {
+            warn!("Could not find {install_folder} on the Asset Library, it is left as is");
+            continue;
+        };
+
+        let confirm = prompt_char!(
+            "Adopt {install_folder} as {} by {} ({})? ('y' to confirm)",
+            result.title,
+            result.author,
+            result.asset_id
+        );
+        if confirm != 'y' {
+            continue;
+        }
+
+        let asset = match asset_library::get_asset_by_id(&registry, &result.asset_id).await {
+            Ok(asset) => AssetInfo {
+                registry: registry_name.clone(),
+                ..asset
+            },
+            Err(e) => {
+                warn!("Skipping {install_folder}: {e}");
+                skipped += 1;
+                continue;
+            }
+        };
+        if let Some(plugin) = &plugin {
+            if !plugin.version.is_empty() && plugin.version != asset.version_string {
+                warn!(
+                    "{install_folder} is version {}, the Asset Library has {}. Run 'godam update {}' to update it",
+                    plugin.version, asset.version_string, result.asset_id
+                );
+            }
+        }
+
+        let install_folders = vec![install_folder];
+        assets::adopt(&result.asset_id, &install_folders)?;
+        config.add_asset(result.asset_id.clone(), asset)?;
+        config.set_install_folders(&result.asset_id, install_folders)?;
+        adopted += 1;
+    }
+
+    match skipped {
+        0 => info!("Adopted {adopted} addon(s)."),
+        skipped => {
+            info!("Adopted {adopted} addon(s), skipped {skipped} that could not be looked up.")
+        }
+    }
+    Ok(())
+}
+
+/// Picks the search result that most likely is the installed plugin: a title match by the
+/// same author, then a title match, then any result by the author, then a lone result.
+fn find_match<'a>(
+    name: &str,
+    author: &str,
+    results: &'a [AssetSearchResult],
+) -> Option<&'a AssetSearchResult> {
+    let is_title_match = |result: &&AssetSearchResult| result.title.eq_ignore_ascii_case(name);
+    let is_author_match = |result: &&AssetSearchResult| {
+        !author.is_empty() && result.author.eq_ignore_ascii_case(author)
+    };
+
+    results
+        .iter()
+        .find(|result| is_title_match(result) && is_author_match(result))
+        .or_else(|| results.iter().find(is_title_match))
+        .or_else(|| results.iter().find(is_author_match))
+        .or(match results {
+            [result] => Some(result),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(asset_id: &str, title: &str, author: &str) -> AssetSearchResult {
+        AssetSearchResult {
+            asset_id: asset_id.to_string(),
+            title: title.to_string(),
+            author: author.to_string(),
+            godot_version: "4.3".to_string(),
+            registry: String::new(),
+        }
+    }
+
+    #[test]
+    fn title_matches_rank_above_author_matches() {
+        let results = [
+            result("1", "Dialogue Tools", "nathan"),
+            result("2", "Dialogue Manager", "someone"),
+            result("3", "Dialogue Manager", "nathan"),
+        ];
+
+        let best = find_match("dialogue manager", "nathan", &results);
+        let by_title = find_match("Dialogue Manager", "else", &results[..2]);
+        let by_author = find_match("Dialogue", "nathan", &results);
+
+        assert_eq!(best.map(|result| result.asset_id.as_str()), Some("3"));
+        assert_eq!(by_title.map(|result| result.asset_id.as_str()), Some("2"));
+        assert_eq!(by_author.map(|result| result.asset_id.as_str()), Some("1"));
+    }
+
+    #[test]
+    fn only_a_lone_result_matches_without_title_or_author() {
+        let results = [result("1", "Terrain", "a"), result("2", "Terrain 3D", "b")];
+
+        assert_eq!(find_match("Heightmaps", "", &results), None);
+        assert_eq!(
+            find_match("Heightmaps", "", &results[..1]).map(|result| result.asset_id.as_str()),
+            Some("1")
+        );
+    }
+}
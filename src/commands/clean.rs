@@ -1,13 +1,13 @@
 use thiserror::Error;
 
-use crate::{assets::cache, config};
+use crate::{assets::cache, config, fs::FsError};
 
 #[derive(Error, Debug)]
 pub enum CleanError {
     #[error(transparent)]
     Config(#[from] config::ConfigError),
     #[error(transparent)]
-    Io(#[from] std::io::Error),
+    Fs(#[from] FsError),
 }
 
 pub fn exec() -> Result<(), CleanError> {
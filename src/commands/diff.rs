@@ -0,0 +1,44 @@
+use diffy::PatchFormatter;
+use thiserror::Error;
+
+use crate::{
+    assets::{diff, AssetError},
+    config::{Config, ConfigError},
+    info,
+};
+
+#[derive(Error, Debug)]
+pub enum DiffError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Asset(#[from] AssetError),
+}
+
+pub async fn exec(id: &str) -> Result<(), DiffError> {
+    let config = Config::get()?;
+
+    let (Some(_), Some(asset)) = (config.get_install_folders(id), config.get_asset_info(id)) else {
+        return Err(AssetError::NotInstalled(id.to_string()).into());
+    };
+    let title = &asset.title;
+
+    let archive = diff::get_original_archive(id, asset).await?;
+
+    let diffs = diff::diff(archive, asset, &[])?;
+    if diffs.is_empty() {
+        info!("{id}: {title} has no local changes");
+        return Ok(());
+    }
+
+    let formatter = match console::Term::stdout().is_term() {
+        true => PatchFormatter::new().with_color(),
+        false => PatchFormatter::new(),
+    };
+    for file_diff in &diffs {
+        print!("{}", formatter.fmt_patch(&file_diff.patch()));
+    }
+
+    Ok(())
+}
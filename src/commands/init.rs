@@ -1,17 +1,45 @@
+use semver::Version;
+
 use crate::{
     config::{self, Config},
+    godot::project::ProjectFile,
     info, warn,
 };
 
-pub fn exec() -> Result<(), config::ConfigError> {
-    if Config::get().is_err() {
-        Config::init()?;
+pub fn exec(godot_version: &Option<Version>) -> Result<(), config::ConfigError> {
+    if let Ok(config) = Config::get() {
+        warn!("godam: Project is already set up to use godam. Search for assets using 'godam search <name>' and install them using 'godam install <ID>'");
+        crate::fs::track_lock_in_gitignore()?;
+        return report_incompatible_assets(&config);
+    }
+
+    Config::init(godot_version)?;
+
+    info!(
+        "godam: Project is now using godam. Search for assets using 'godam search <name>' and install them using 'godam install <ID>'"
+    );
+    Ok(())
+}
 
-        info!(
-            "godam: Project is now using godam. Search for assets using 'godam search <name>' and install them using 'godam install <ID>'"
+/// Points out assets that do not match the Godot version of an already initialized project.
+fn report_incompatible_assets(config: &Config) -> Result<(), config::ConfigError> {
+    let godot_version = &config.godot_version;
+    let project_version = ProjectFile::get()?.get_version()?;
+    if (project_version.major, project_version.minor) != (godot_version.major, godot_version.minor)
+    {
+        warn!(
+            "godam: project.godot uses Godot {}.{}, but godam.toml targets Godot {}.{}",
+            project_version.major, project_version.minor, godot_version.major, godot_version.minor
         );
-    } else {
-        warn!("godam: Project is already set up to use godam. Search for assets using 'godam search <name>' and install them using 'godam install <ID>'");
+    }
+
+    for asset in config.asset_infos.values() {
+        if !asset.is_compatible_with(godot_version) {
+            warn!(
+                "godam: {} was made for Godot {}, but the project targets Godot {}.{}",
+                asset.title, asset.godot_version, godot_version.major, godot_version.minor
+            );
+        }
     }
     Ok(())
 }
@@ -1,23 +1,26 @@
-use std::{
-    io::Cursor,
-    sync::{Arc, Mutex},
-};
+use std::collections::{BTreeMap, BTreeSet};
 
 use indicatif::{MultiProgress, ProgressBar};
 use thiserror::Error;
 use tokio::task::JoinSet;
-use zip::ZipArchive;
 
 use crate::{
     assets::{
         self,
         cache::{self, AssetArchive},
-        get_install_folders_in_project, AssetInfo,
+        checksum, conflict, get_install_folders_in_project,
+        manifest::InstallManifest,
+        AssetInfo, Overwrite,
     },
     config::{self, Config},
     console::{progress_style, GodamProgressMessage},
-    godot::asset_library::{self, AssetLibraryError},
-    traits::ReadSeek,
+    fs::FsError,
+    godot::{
+        asset_library::{self, AssetLibraryError},
+        project::{self, GodotProjectError},
+    },
+    lock::{Lock, LockError, LockedAsset},
+    sources::{self, SourceError},
     warn,
 };
 
@@ -26,30 +29,63 @@ pub enum InstallError {
     #[error(transparent)]
     Config(#[from] config::ConfigError),
 
+    #[error(transparent)]
+    Lock(#[from] LockError),
+
     #[error(transparent)]
     Request(#[from] AssetLibraryError),
 
+    #[error(transparent)]
+    Source(#[from] SourceError),
+
     #[error("Cache error: {0}")]
     Cache(#[from] std::io::Error),
 
+    #[error(transparent)]
+    Fs(#[from] FsError),
+
     #[error(transparent)]
     Zip(#[from] zip::result::ZipError),
 
     #[error(transparent)]
     Asset(#[from] assets::AssetError),
 
-    #[error("An error occured when locking resources for a thread.")]
-    Mutex,
+    #[error(transparent)]
+    Project(#[from] GodotProjectError),
+
+    #[error("{0} asset(s) failed to install")]
+    Failed(usize),
 }
 
-pub async fn exec(ids: &Option<Vec<String>>) -> Result<(), InstallError> {
+pub async fn exec(
+    ids: &Option<Vec<String>>,
+    registry_name: &Option<String>,
+    force: bool,
+    reinstall: bool,
+) -> Result<(), InstallError> {
     let mut config = Config::get()?;
+    let mut failed = 0;
+    // new assets only join the config once they are installed
+    let mut new_assets = BTreeMap::new();
 
     if let Some(ids) = ids {
+        let registry = config.get_registry(registry_name.as_deref())?;
         for id in ids {
             if config.get_asset_info(id).is_none() {
-                match asset_library::get_asset_by_id(id).await {
-                    Ok(asset) => config.add_asset(id.to_string(), asset)?,
+                match asset_library::get_asset_by_id(&registry, id).await {
+                    Ok(asset) => {
+                        let asset = AssetInfo {
+                            registry: registry_name.clone(),
+                            ..asset
+                        };
+                        // refused assets are not added, so they do not fail every later install
+                        if let Err(e) = assets::check_compatibility(&asset, &config, force) {
+                            warn!("{e}");
+                            failed += 1;
+                            continue;
+                        }
+                        new_assets.insert(id.clone(), asset);
+                    }
                     Err(e) => warn!("{e}"),
                 }
             }
@@ -58,99 +94,248 @@ pub async fn exec(ids: &Option<Vec<String>>) -> Result<(), InstallError> {
 
     let progress = MultiProgress::new();
 
-    let assets = Config::get()?.asset_infos;
+    let mut assets = config.asset_infos.clone();
+    assets.extend(new_assets.clone());
     let install_folders = get_install_folders_in_project()?;
 
+    let mut lock = Lock::get()?;
+    let manifests = InstallManifest::get_all()?;
+
+    // reinstalls cover the given assets, or all of them
+    let is_reinstalled = |id: &String| match ids {
+        Some(ids) => (force || reinstall) && ids.contains(id),
+        None => force || reinstall,
+    };
+    let get_overwrite = |id: &String| match (is_reinstalled(id), force) {
+        (true, true) => Overwrite::All,
+        (true, false) => Overwrite::Modified,
+        (false, _) => Overwrite::Owned,
+    };
+    // a teammate pinned another archive, so the installed one is replaced
+    let is_lock_bumped = |id: &String| {
+        let locked_sha256 = lock.get_locked_asset(id).map(|locked| &locked.sha256);
+        let installed_sha256 = manifests
+            .get(id)
+            .and_then(|manifest| manifest.sha256.as_ref());
+        matches!((locked_sha256, installed_sha256), (Some(locked), Some(installed)) if locked != installed)
+    };
+    // the branch, tag or commit was changed in godam.toml, so the pinned commit no longer applies
+    let is_repinned = |id: &String, asset: &AssetInfo| {
+        lock.get_locked_asset(id)
+            .is_some_and(|locked| !locked.pins(asset))
+    };
+
     let not_installed_assets: Vec<(String, AssetInfo)> = assets
         .into_iter()
         .filter_map(|entry| {
-            let Some(folder) = config.get_install_folder(&entry.0) else {
+            let Some(folders) = config.get_install_folders(&entry.0) else {
                 return Some(entry);
             };
 
-            if install_folders.contains(folder) {
+            if !is_reinstalled(&entry.0)
+                && !is_lock_bumped(&entry.0)
+                && !is_repinned(&entry.0, &entry.1)
+                && folders
+                    .iter()
+                    .all(|folder| install_folders.contains(folder))
+            {
                 None
             } else {
                 Some(entry)
             }
         })
+        .filter(|(id, asset)| {
+            if new_assets.contains_key(id) {
+                return true;
+            }
+            match assets::check_compatibility(asset, &config, force) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("{e}");
+                    failed += 1;
+                    false
+                }
+            }
+        })
         .collect();
 
-    let config = Arc::new(Mutex::new(config));
-
     let mut tasks = JoinSet::new();
-
     for (id, asset) in not_installed_assets {
-        let config = config.clone();
+        let locked_asset = lock.get_locked_asset(&id).cloned();
         let pb = progress.add(ProgressBar::new_spinner().with_style(progress_style()));
         tasks.spawn(async move {
             pb.enable_steady_tick(std::time::Duration::from_millis(100));
-            match install_asset(&id, &asset, &pb, config).await {
-                Ok(()) => pb.complete("Installed", &asset.title),
-                Err(e) => pb.fail(&asset.title, &e.to_string()),
-            };
+            let fetched = fetch_archive(&id, &asset, locked_asset.as_ref(), &pb).await;
+            (id, asset, pb, fetched)
         });
     }
 
-    tasks.join_all().await;
+    // every archive is fetched before anything is installed, so conflicts are caught up front
+    let mut fetched_assets = Vec::new();
+    let mut pending_files = BTreeMap::new();
+    for (id, asset, pb, fetched) in tasks.join_all().await {
+        let fetched = fetched.and_then(|fetched| {
+            let (_, files) = fetched
+                .archive
+                .get_plugin_name_and_files_to_extract(&asset)?;
+            Ok((fetched, files))
+        });
+        match fetched {
+            Ok((fetched, files)) => {
+                let files = files.into_iter().map(|(_, out_path)| out_path).collect();
+                pending_files.insert(id.clone(), files);
+                fetched_assets.push((id, asset, pb, fetched));
+            }
+            Err(e) => {
+                pb.fail(&asset.title, &e.to_string());
+                failed += 1;
+            }
+        }
+    }
 
-    Ok(())
+    let mut skipped = match conflict::resolve(&pending_files, &config.precedence) {
+        Ok(skipped) => skipped,
+        Err(e) => {
+            for (_, asset, pb, _) in &fetched_assets {
+                pb.fail(&asset.title, "Not installed, conflicting files");
+            }
+            return Err(e.into());
+        }
+    };
+
+    for (id, asset, pb, fetched) in fetched_assets {
+        let skipped = skipped.remove(&id).unwrap_or_default();
+        pb.start("Unpacking", &asset.title);
+        let overwrite = get_overwrite(&id);
+        let installed = install_asset(
+            &id,
+            &asset,
+            fetched,
+            &skipped,
+            overwrite,
+            &mut config,
+            &mut lock,
+        );
+        match installed {
+            Ok(()) => pb.complete("Installed", &asset.title),
+            Err(e) => {
+                pb.fail(&asset.title, &e.to_string());
+                failed += 1;
+            }
+        }
+    }
+
+    // also covers assets that were installed before they opted in
+    project::enable_plugins(&Config::get()?.get_plugin_folders())?;
+
+    match failed {
+        0 => Ok(()),
+        count => Err(InstallError::Failed(count)),
+    }
 }
 
-async fn install_asset(
+/// An asset archive ready to be installed.
+struct FetchedArchive {
+    archive: AssetArchive,
+    /// The revision the source resolved the asset to, if it was not read from the cache.
+    resolved_version: Option<String>,
+}
+
+async fn fetch_archive(
     id: &str,
     asset: &AssetInfo,
+    locked_asset: Option<&LockedAsset>,
     progress: &ProgressBar,
-    config: Arc<Mutex<Config>>,
-) -> Result<(), InstallError> {
+) -> Result<FetchedArchive, InstallError> {
     progress.start("Fetching", &asset.title);
-    let archive: AssetArchive = match cache::get(id) {
-        Ok(hit) => hit,
-
-        Err(_) => {
-            let blob = asset_library::download(asset).await?;
-            cache::write_to_cache(id, &blob)?;
-            let cursor: Box<dyn ReadSeek> = Box::new(Cursor::new(blob.bytes));
-            AssetArchive {
-                id: id.to_string(),
-                archive: ZipArchive::new(cursor)?,
-            }
+    // the digests recorded for another branch, tag or commit no longer apply
+    let is_repinned = locked_asset.is_some_and(|locked| !locked.pins(asset));
+    let locked_asset = locked_asset.filter(|locked| locked.pins(asset));
+    let expected_sha256 = match locked_asset {
+        Some(locked) => Some(locked.sha256.as_str()),
+        None if is_repinned => None,
+        None => asset.sha256.as_deref(),
+    };
+
+    let cached = match asset.is_cacheable() {
+        true => cache::get(id)?,
+        false => None,
+    };
+
+    // an archive cached before the lock was bumped is fetched again
+    let cached = match cached {
+        Some(_) if is_repinned => {
+            cache::remove(id)?;
+            None
+        }
+        Some(hit) if checksum::verify(id, expected_sha256, &hit.sha256).is_err() => {
+            cache::remove(id)?;
+            None
         }
+        cached => cached,
     };
 
-    // register install folder before installing
-    match config.lock() {
-        Ok(mut config) => {
-            let install_folder_name = match archive.get_plugin_info() {
-                Some((name, _)) => name,
-                None => {
-                    return Err(InstallError::Asset(
-                        assets::AssetError::InvalidAssetStructure(
-                            "Could not find plugin name".to_string(),
-                        ),
-                    ))
-                }
-            };
-            if let Err(e) = config.set_install_folder(id, install_folder_name) {
-                progress.fail(&asset.title, &e.to_string());
+    match cached {
+        Some(hit) => Ok(FetchedArchive {
+            archive: hit,
+            resolved_version: None,
+        }),
+
+        None => {
+            // the source prefers the pinned entry, so every machine installs the same archive
+            let blob = sources::fetch(id, asset, locked_asset).await?;
+            let archive = AssetArchive::from_blob(id, &blob)?;
+            if asset.is_cacheable() {
+                checksum::verify(id, expected_sha256, &blob.sha256)?;
+                cache::write_to_cache(id, &blob)?;
             }
-        }
-        Err(e) => {
-            progress.fail(&asset.title, &e.to_string());
-            return Err(InstallError::Mutex);
+            Ok(FetchedArchive {
+                archive,
+                resolved_version: blob.version,
+            })
         }
     }
+}
 
-    progress.start("Unpacking", &asset.title);
-    match assets::install(archive).map_err(InstallError::from) {
-        Ok(folder) => folder,
-        Err(e) => {
-            progress.fail(&asset.title, &e.to_string());
-            return Err(e);
-        }
+fn install_asset(
+    id: &str,
+    asset: &AssetInfo,
+    fetched: FetchedArchive,
+    skipped: &BTreeSet<String>,
+    overwrite: Overwrite,
+    config: &mut Config,
+    lock: &mut Lock,
+) -> Result<(), InstallError> {
+    let sha256 = fetched.archive.sha256.clone();
+    let is_repinned = lock
+        .get_locked_asset(id)
+        .is_some_and(|locked| !locked.pins(asset));
+
+    // files left from an earlier install are replaced, so none of them go stale
+    let installed_folders = match config.get_install_folders(id) {
+        Some(previous_install_folders) => assets::replace(
+            fetched.archive,
+            asset,
+            previous_install_folders,
+            skipped,
+            overwrite,
+        )?,
+        None => assets::install(fetched.archive, asset, skipped, overwrite)?,
     };
 
-    progress.complete("Installed", &asset.title);
+    // only record the asset once it is fully in place
+    if (lock.get_locked_asset(id).is_none() || is_repinned) && asset.is_cacheable() {
+        let locked = LockedAsset::new(asset, sha256.clone(), fetched.resolved_version);
+        lock.lock_asset(id, locked)?;
+    }
+
+    if config.get_asset_info(id).is_none() {
+        config.add_asset(id.to_string(), asset.clone())?;
+    }
+    if (asset.sha256.is_none() || is_repinned) && asset.is_cacheable() {
+        config.set_sha256(id, sha256)?;
+    }
+    config.set_install_folders(id, installed_folders)?;
 
     Ok(())
 }
@@ -0,0 +1,182 @@
+use std::fmt::Display;
+
+use semver::Version;
+use thiserror::Error;
+
+use crate::{
+    assets::AssetInfo,
+    commands::update::{self, UpdateError},
+    config::{Config, ConfigError},
+    console::format_table,
+    godot::asset_library::{self, AssetLibraryError},
+    info, warn,
+};
+
+#[derive(Error, Debug)]
+pub enum MigrateError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Request(#[from] AssetLibraryError),
+
+    #[error(transparent)]
+    Update(#[from] UpdateError),
+}
+
+enum Status {
+    Compatible,
+    Incompatible,
+    Unknown,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Compatible => write!(f, "Compatible"),
+            Status::Incompatible => write!(f, "Incompatible"),
+            Status::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// Checks every asset against a new Godot version and makes it the version `godam.toml` targets.
+///
+/// An asset is compatible if the Asset Library lists it when filtering by the new version.
+/// With `reinstall`, compatible assets are updated to the latest release, keeping files that
+/// were modified after install.
+pub async fn exec(godot_version: &Version, reinstall: bool) -> Result<(), MigrateError> {
+    let mut config = Config::get()?;
+
+    let mut rows = Vec::new();
+    let mut compatible_ids = Vec::new();
+    for (id, asset) in &config.asset_infos {
+        let (status, detail) = check_asset(id, asset, &config, godot_version).await;
+        if matches!(status, Status::Compatible) {
+            compatible_ids.push(id.clone());
+        }
+        rows.push([id.clone(), asset.title.clone(), status.to_string(), detail]);
+    }
+
+    for line in format_table(["ID", "Title", "Status", "Made for"], &rows) {
+        info!("{line}");
+    }
+    if compatible_ids.len() < rows.len() {
+        warn!(
+            "{} asset(s) may not work with Godot {}.{}",
+            rows.len() - compatible_ids.len(),
+            godot_version.major,
+            godot_version.minor
+        );
+    }
+
+    config.godot_version = godot_version.clone();
+    config.save()?;
+    info!(
+        "godam.toml now targets Godot {}.{}",
+        godot_version.major, godot_version.minor
+    );
+
+    if reinstall && !compatible_ids.is_empty() {
+        update::exec(&Some(compatible_ids), false).await?;
+    }
+
+    Ok(())
+}
+
+/// Searches the asset's registry for releases made for the new version, returning the status
+/// and the Godot version the release was made for, or why the status is unknown.
+async fn check_asset(
+    id: &str,
+    asset: &AssetInfo,
+    config: &Config,
+    godot_version: &Version,
+) -> (Status, String) {
+    if !asset.is_from_asset_library() {
+        return (Status::Unknown, "not on the Asset Library".to_string());
+    }
+
+    let registry = match config.get_registry(asset.registry.as_deref()) {
+        Ok(registry) => registry,
+        Err(e) => return (Status::Unknown, e.to_string()),
+    };
+
+    let results =
+        match asset_library::get_assets_by_name(&registry, &asset.title, godot_version).await {
+            Ok(results) => results,
+            Err(e) => return (Status::Unknown, e.to_string()),
+        };
+    if let Some(result) = results.into_iter().find(|result| result.asset_id == id) {
+        return (Status::Compatible, result.godot_version);
+    }
+
+    // not listed for the new version, so report what the latest release was made for
+    match asset_library::get_asset_by_id(&registry, id).await {
+        Ok(latest) if latest.godot_version.is_empty() => {
+            (Status::Unknown, "no version declared".to_string())
+        }
+        Ok(latest) => (Status::Incompatible, latest.godot_version),
+        Err(e) => (Status::Unknown, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        commands::install,
+        fs::path::{get_config_path, get_install_folder_path},
+        test_utils::{blob, serve, zip_writer, TestProject, TestResult},
+    };
+
+    use super::*;
+
+    #[test]
+    fn reinstalls_keep_modified_files() -> TestResult {
+        let _project = TestProject::new()?;
+        let release = |contents| -> TestResult<Vec<u8>> {
+            let files = [
+                ("addons/tool/owned.gd", contents),
+                ("addons/tool/edited.gd", contents),
+            ];
+            Ok(blob(zip_writer(&files)?)?.bytes)
+        };
+        let url = serve(|url| {
+            let search = r#"{"result":[{"asset_id":"1","title":"Tool","godot_version":"4.3"}]}"#;
+            let latest = format!(
+                r#"{{"title":"Tool","download_url":"{url}/2.zip","version":"2","godot_version":"4.3"}}"#
+            );
+            Ok(vec![
+                ("/api/asset?", search.into()),
+                ("/api/asset/1", latest.into()),
+                ("/1.zip", release("1")?),
+                ("/2.zip", release("2")?),
+            ])
+        })?;
+        let config = format!(
+            r#"godot_version = "4.2.0"
+registry = "{url}/api"
+
+[asset_infos.1]
+title = "Tool"
+download_url = "{url}/1.zip"
+version = "1"
+godot_version = "4.2"
+
+[install_folders]
+"#
+        );
+        std::fs::write(get_config_path(), config)?;
+
+        tokio::runtime::Runtime::new()?.block_on(async {
+            install::exec(&None, &None, false, false).await?;
+            std::fs::write(get_install_folder_path("tool").join("edited.gd"), "mine")?;
+            exec(&Version::new(4, 3, 0), true).await?;
+            TestResult::Ok(())
+        })?;
+
+        let read = |file| std::fs::read_to_string(get_install_folder_path("tool").join(file));
+        assert_eq!(read("owned.gd")?, "2");
+        assert_eq!(read("edited.gd")?, "mine");
+        Ok(())
+    }
+}
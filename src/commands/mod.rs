@@ -1,17 +1,31 @@
+pub mod adopt;
 pub mod clean;
+pub mod diff;
 pub mod init;
 pub mod install;
 pub mod list;
+pub mod migrate;
+pub mod outdated;
+pub mod patch;
 pub mod search;
 pub mod uninstall;
+pub mod update;
+pub mod verify;
 
 use clap::Subcommand;
+use semver::Version;
+
+use crate::godot::project::parse_version;
 
 #[derive(Subcommand)]
 pub enum Command {
     #[command()]
     /// Initializes your Godot project to use godam as your addon manager
-    Init,
+    Init {
+        /// The Godot version the project targets, e.g. 3.5. Read from project.godot by default
+        #[arg(long, value_parser = parse_version)]
+        godot: Option<Version>,
+    },
     /// Searches the Godot Asset Library API for assets by name.
     #[command(alias = "s")]
     Search {
@@ -25,6 +39,21 @@ pub enum Command {
         /// The name of the asset you want to install
         #[arg(index = 1)]
         name: Option<Vec<String>>,
+        /// The registry to look up new assets in, as named in godam.toml
+        #[arg(long, short)]
+        registry: Option<String>,
+        /// Wipes the install folders of the given assets (or all of them) and reinstalls them from cache, removing files you added. Also installs assets made for another Godot version without a warning
+        #[arg(long, short)]
+        force: bool,
+        /// Reinstalls the given assets (or all of them) from cache, overwriting files that were modified after install but keeping files you added
+        #[arg(long)]
+        reinstall: bool,
+    },
+    /// Adds addons that were installed by hand to the godam configuration, matching them to Asset Library assets.
+    Adopt {
+        /// The registry to look up the addons in, as named in godam.toml
+        #[arg(long, short)]
+        registry: Option<String>,
     },
     /// Uninstalls the specified addon from your Godot project, removing it from the godam configuration.
     #[command(alias = "u")]
@@ -33,6 +62,46 @@ pub enum Command {
         #[arg(index = 1)]
         name: Option<String>,
     },
+    /// Updates the specified addons to their latest release. Omit the ID to update all addons.
+    #[command(alias = "up")]
+    Update {
+        /// The IDs of the assets you want to update
+        #[arg(index = 1)]
+        name: Option<Vec<String>>,
+        /// Installs releases made for another Godot version without a warning
+        #[arg(long, short)]
+        force: bool,
+    },
+    /// Lists all addons with a newer release available on the Asset Library
+    Outdated,
+    /// Checks every addon against a new Godot version and makes godam target it.
+    Migrate {
+        /// The Godot version to migrate to, e.g. 4.3
+        #[arg(long, value_parser = parse_version)]
+        godot: Version,
+        /// Updates the compatible addons to their latest release afterwards
+        #[arg(long)]
+        reinstall: bool,
+    },
+    /// Checks installed addons for files that were modified, removed or added since install. Omit the ID to verify all addons.
+    Verify {
+        /// The ID of the asset you want to verify
+        #[arg(index = 1)]
+        name: Option<String>,
+    },
+    /// Shows local changes to the text files of an installed addon as a unified diff.
+    #[command(alias = "d")]
+    Diff {
+        /// The ID of the asset you want to diff
+        #[arg(index = 1)]
+        name: String,
+    },
+    /// Records the local changes to an installed addon as a patch that is applied on every install.
+    Patch {
+        /// The ID of the asset you want to record a patch for
+        #[arg(index = 1)]
+        name: String,
+    },
     /// Lists all assets being managed by Godam
     #[command(alias = "ls", alias = "l")]
     ///
@@ -0,0 +1,58 @@
+use thiserror::Error;
+
+use crate::{
+    config::{Config, ConfigError},
+    console::format_table,
+    godot::asset_library::{self, AssetLibraryError},
+    info, warn,
+};
+
+#[derive(Error, Debug)]
+pub enum OutdatedError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Request(#[from] AssetLibraryError),
+}
+
+pub async fn exec() -> Result<(), OutdatedError> {
+    let config = Config::get()?;
+
+    let mut rows = Vec::new();
+    for (id, asset) in &config.asset_infos {
+        if !asset.is_from_asset_library() {
+            continue;
+        }
+
+        let registry = match config.get_registry(asset.registry.as_deref()) {
+            Ok(registry) => registry,
+            Err(e) => {
+                warn!("{id}: {e}");
+                continue;
+            }
+        };
+
+        match asset_library::get_asset_by_id(&registry, id).await {
+            Ok(latest) if asset.is_outdated_by(&latest) => rows.push([
+                id.clone(),
+                asset.title.clone(),
+                asset.version_string.clone(),
+                latest.version_string,
+            ]),
+            Ok(_) => (),
+            Err(e) => warn!("{id}: {e}"),
+        }
+    }
+
+    if rows.is_empty() {
+        info!("All assets are up to date.");
+        return Ok(());
+    }
+
+    for line in format_table(["ID", "Title", "Current", "Available"], &rows) {
+        info!("{line}");
+    }
+
+    Ok(())
+}
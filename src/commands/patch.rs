@@ -0,0 +1,77 @@
+use thiserror::Error;
+
+use crate::{
+    assets::{diff, patch, to_manifest_path, AssetError},
+    config::{Config, ConfigError},
+    fs::{
+        path::{get_patch_path, get_patches_path},
+        FsError,
+    },
+    info,
+};
+
+#[derive(Error, Debug)]
+pub enum PatchError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Asset(#[from] AssetError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Fs(#[from] FsError),
+}
+
+pub async fn exec(id: &str) -> Result<(), PatchError> {
+    let mut config = Config::get()?;
+
+    let (Some(_), Some(asset)) = (config.get_install_folders(id), config.get_asset_info(id)) else {
+        return Err(AssetError::NotInstalled(id.to_string()).into());
+    };
+    let asset = asset.clone();
+    let title = &asset.title;
+
+    let archive = diff::get_original_archive(id, &asset).await?;
+
+    // patches changing files the diff cannot represent are kept, and the diff is taken on top of them
+    let patch_path = get_patch_path(id);
+    let mut patches = Vec::new();
+    for patch in &asset.patches {
+        if *patch == to_manifest_path(&patch_path) {
+            continue;
+        }
+        let patched_files = patch::get_patched_files(patch)?;
+        if !patched_files.iter().all(|file| diff::is_text_file(file)) {
+            patches.push(patch.clone());
+        }
+    }
+
+    let diffs = diff::diff(archive, &asset, &patches)?;
+    if diffs.is_empty() {
+        info!("{id}: {title} has no local changes to record");
+        return Ok(());
+    }
+
+    let patches_path = get_patches_path();
+    if !crate::fs::exists(patches_path)? {
+        crate::fs::safe_create_dir(patches_path)?;
+    }
+
+    crate::fs::safe_write(&patch_path, patch::create(&diffs))?;
+    for patch in &patches {
+        info!("{id}: Keeping {patch}, it changes files that cannot be diffed");
+    }
+    patches.push(to_manifest_path(&patch_path));
+    config.set_patches(id, patches)?;
+
+    info!(
+        "{id}: Recorded changes to {} file(s) of {title} in {}",
+        diffs.len(),
+        patch_path.display()
+    );
+
+    Ok(())
+}
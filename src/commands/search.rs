@@ -1,11 +1,17 @@
+use std::collections::BTreeMap;
+
 use thiserror::Error;
 
 use crate::{
+    config::Config,
     godot::{
-        asset_library::{get_assets_by_name, AssetLibraryError, AssetSearchResult},
-        project::{get_version, GodotProjectError},
+        asset_library::{
+            get_assets_by_name, get_registry, AssetLibraryError, AssetSearchResult,
+            DEFAULT_REGISTRY_NAME,
+        },
+        project::{GodotProjectError, ProjectFile},
     },
-    info,
+    info, warn,
 };
 #[derive(Error, Debug)]
 pub enum SearchError {
@@ -16,11 +22,38 @@ pub enum SearchError {
 }
 
 pub async fn exec(asset_name: &str) -> Result<(), SearchError> {
-    let version = get_version()?;
-    let assets = get_assets_by_name(asset_name, &version).await?;
+    let (version, registries) = match Config::get() {
+        Ok(config) => (config.godot_version.clone(), config.get_registries()),
+        Err(_) => (
+            ProjectFile::get()?.get_version()?,
+            BTreeMap::from([(DEFAULT_REGISTRY_NAME.to_string(), get_registry(None))]),
+        ),
+    };
+
+    let mut assets = Vec::new();
+    for (name, registry) in &registries {
+        match get_assets_by_name(registry, asset_name, &version).await {
+            Ok(results) => assets.extend(results.into_iter().map(|result| AssetSearchResult {
+                registry: name.clone(),
+                ..result
+            })),
+            Err(e) if registries.len() > 1 => warn!("{name}: {e}"),
+            Err(e) => return Err(e.into()),
+        }
+    }
 
-    for AssetSearchResult { title, asset_id } in &assets {
-        info!("{asset_id}: {title}");
+    for AssetSearchResult {
+        title,
+        asset_id,
+        registry,
+        ..
+    } in &assets
+    {
+        if registries.len() > 1 {
+            info!("{asset_id}: {title} ({registry})");
+        } else {
+            info!("{asset_id}: {title}");
+        }
     }
 
     Ok(())
@@ -5,6 +5,8 @@ use crate::{
     assets::{self, AssetError},
     config::{Config, ConfigError},
     console::{progress_style, GodamProgressMessage},
+    godot::project,
+    lock::{Lock, LockError},
     prompt_char,
 };
 
@@ -18,26 +20,30 @@ pub enum UninstallError {
 
     #[error(transparent)]
     AssetError(#[from] AssetError),
+
+    #[error(transparent)]
+    Lock(#[from] LockError),
 }
 
 pub fn exec(id: &Option<String>) -> Result<(), UninstallError> {
     let mut config = Config::get()?;
+    let mut lock = Lock::get()?;
 
     let progress = MultiProgress::new();
 
     match id {
-        Some(some_id) => uninstall_single(some_id, &mut config, &progress),
+        Some(some_id) => uninstall_single(some_id, &mut config, &mut lock, &progress),
         None => {
             let confirm = prompt_char!("Do you want to uninstall all addons? ('y' to confirm)");
             if confirm == 'y' {
-                uninstall_all(&mut config, &progress)?;
+                uninstall_all(&mut config, &mut lock, &progress)?;
             }
         }
     }
     Ok(())
 }
 
-fn uninstall_single(id: &str, config: &mut Config, progress: &MultiProgress) {
+fn uninstall_single(id: &str, config: &mut Config, lock: &mut Lock, progress: &MultiProgress) {
     let pb = progress.add(ProgressBar::new_spinner().with_style(progress_style()));
 
     let asset = match config.get_asset_info(id) {
@@ -49,12 +55,18 @@ fn uninstall_single(id: &str, config: &mut Config, progress: &MultiProgress) {
     };
 
     pb.start("Uninstalling", &asset.title);
+    let install_folders = config.get_install_folders(id).cloned();
     match assets::uninstall(id.to_string()) {
         Ok(()) => (),
         Err(e) => {
             pb.fail(id, &e.to_string());
         }
     }
+    if let (true, Some(install_folders)) = (asset.enable, install_folders) {
+        if let Err(e) = project::disable_plugins(&install_folders) {
+            pb.fail(&asset.title, &e.to_string());
+        }
+    }
     pb.start("Removing", &asset.title);
     match config.remove_asset(id) {
         Ok(_) => (),
@@ -63,12 +75,47 @@ fn uninstall_single(id: &str, config: &mut Config, progress: &MultiProgress) {
             return;
         }
     }
+    if let Err(e) = lock.unlock_asset(id) {
+        pb.fail(&asset.title, &e.to_string());
+        return;
+    }
     pb.complete("Removed", &asset.title);
 }
 
-fn uninstall_all(config: &mut Config, progress: &MultiProgress) -> Result<(), UninstallError> {
+fn uninstall_all(
+    config: &mut Config,
+    lock: &mut Lock,
+    progress: &MultiProgress,
+) -> Result<(), UninstallError> {
     for asset in config.asset_infos.clone() {
-        uninstall_single(&asset.0, config, progress);
+        uninstall_single(&asset.0, config, lock, progress);
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{TestProject, TestResult};
+
+    use super::*;
+
+    #[test]
+    fn modified_files_are_kept() -> TestResult {
+        let project = TestProject::new()?;
+        project.install(&[
+            ("addons/tool/owned.gd", "1"),
+            ("addons/tool/edited.gd", "1"),
+        ])?;
+        std::fs::write("addons/tool/edited.gd", "mine")?;
+
+        exec(&Some("1234".to_string()))?;
+
+        assert!(!crate::fs::exists("addons/tool/owned.gd".as_ref())?);
+        assert_eq!(
+            crate::fs::read_string("addons/tool/edited.gd".as_ref())?,
+            "mine"
+        );
+        assert!(Config::get()?.get_asset_info("1234").is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use indicatif::{MultiProgress, ProgressBar};
+use thiserror::Error;
+
+use crate::{
+    assets::{self, cache, conflict, AssetError, AssetInfo, Overwrite},
+    config::{Config, ConfigError},
+    console::{progress_style, GodamProgressMessage},
+    fs::FsError,
+    godot::{
+        asset_library::{self, AssetLibraryError},
+        project::{self, GodotProjectError},
+    },
+    lock::{Lock, LockError, LockedAsset},
+    sources::{self, SourceError},
+};
+
+#[derive(Error, Debug)]
+pub enum UpdateError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Lock(#[from] LockError),
+
+    #[error(transparent)]
+    Request(#[from] AssetLibraryError),
+
+    #[error(transparent)]
+    Source(#[from] SourceError),
+
+    #[error("Cache error: {0}")]
+    Cache(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Fs(#[from] FsError),
+
+    #[error(transparent)]
+    Asset(#[from] AssetError),
+
+    #[error(transparent)]
+    Project(#[from] GodotProjectError),
+
+    #[error("No addon found with id {0}")]
+    NotFound(String),
+
+    #[error("{0} asset(s) failed to update")]
+    Failed(usize),
+}
+
+pub async fn exec(ids: &Option<Vec<String>>, force: bool) -> Result<(), UpdateError> {
+    let mut config = Config::get()?;
+    let mut lock = Lock::get()?;
+
+    let ids: Vec<String> = match ids {
+        Some(ids) => ids.clone(),
+        None => config.asset_infos.keys().cloned().collect(),
+    };
+
+    let progress = MultiProgress::new();
+
+    let mut failed = 0;
+    for id in ids {
+        let pb = progress.add(ProgressBar::new_spinner().with_style(progress_style()));
+        pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+        let Some(asset) = config.get_asset_info(&id).cloned() else {
+            pb.fail(&id, &UpdateError::NotFound(id.clone()).to_string());
+            failed += 1;
+            continue;
+        };
+
+        match update_asset(&id, &asset, force, &pb, &mut config, &mut lock).await {
+            Ok(Some(latest)) if latest.version_string.is_empty() => {
+                pb.complete("Updated", &latest.title)
+            }
+            Ok(Some(latest)) => pb.complete(
+                "Updated",
+                &format!(
+                    "{} ({} -> {})",
+                    latest.title, asset.version_string, latest.version_string
+                ),
+            ),
+            Ok(None) => pb.complete("Up to date", &asset.title),
+            Err(e) => {
+                pb.fail(&asset.title, &e.to_string());
+                failed += 1;
+            }
+        }
+    }
+
+    project::enable_plugins(&config.get_plugin_folders())?;
+
+    match failed {
+        0 => Ok(()),
+        count => Err(UpdateError::Failed(count)),
+    }
+}
+
+/// Replaces an installed asset with the latest version from its source.
+///
+/// The previous install is restored if the new version fails to install, and the
+/// config and lock entries are only rewritten once the new version is in place.
+async fn update_asset(
+    id: &str,
+    asset: &AssetInfo,
+    force: bool,
+    progress: &ProgressBar,
+    config: &mut Config,
+    lock: &mut Lock,
+) -> Result<Option<AssetInfo>, UpdateError> {
+    progress.start("Checking", &asset.title);
+    let (mut latest, blob) = if asset.is_from_asset_library() {
+        let registry = config.get_registry(asset.registry.as_deref())?;
+        let latest = asset.with_release(asset_library::get_asset_by_id(&registry, id).await?);
+        if !asset.is_outdated_by(&latest) {
+            return Ok(None);
+        }
+        assets::check_compatibility(&latest, config, force)?;
+
+        progress.start("Fetching", &latest.title);
+        let blob = asset_library::download(&latest.download_url).await?;
+        (latest, blob)
+    } else {
+        // other sources carry no version, so fetch past the lock and compare digests instead
+        let blob = sources::fetch(id, asset, None).await?;
+        let current_sha256 = match lock.get_locked_asset(id) {
+            Some(locked) => Some(locked.sha256.as_str()),
+            None => asset.sha256.as_deref(),
+        };
+        if current_sha256 == Some(blob.sha256.as_str()) {
+            return Ok(None);
+        }
+
+        (asset.clone(), blob)
+    };
+
+    let archive = cache::AssetArchive::from_blob(id, &blob)?;
+    let (_, files) = archive.get_plugin_name_and_files_to_extract(&latest)?;
+    let pending_files = BTreeMap::from([(
+        id.to_string(),
+        files.into_iter().map(|(_, out_path)| out_path).collect(),
+    )]);
+    let skipped = conflict::resolve(&pending_files, &config.precedence)?
+        .remove(id)
+        .unwrap_or_default();
+
+    progress.start("Unpacking", &latest.title);
+    let previous_install_folders = config.get_install_folders(id).cloned();
+    let install_folders = match &previous_install_folders {
+        Some(previous_install_folders) => assets::replace(
+            archive,
+            &latest,
+            previous_install_folders,
+            &skipped,
+            Overwrite::Owned,
+        )?,
+        None => assets::install(archive, &latest, &skipped, Overwrite::Owned)?,
+    };
+
+    if latest.is_cacheable() {
+        cache::write_to_cache(id, &blob)?;
+        latest.sha256 = Some(blob.sha256.clone());
+        lock.lock_asset(id, LockedAsset::new(&latest, blob.sha256, blob.version))?;
+    }
+    config.set_install_folders(id, install_folders.clone())?;
+    config.add_asset(id.to_string(), latest.clone())?;
+
+    if let Some(previous_install_folders) = previous_install_folders {
+        let removed_folders: Vec<String> = previous_install_folders
+            .into_iter()
+            .filter(|folder| !install_folders.contains(folder))
+            .collect();
+        if latest.enable && !removed_folders.is_empty() {
+            project::disable_plugins(&removed_folders)?;
+        }
+    }
+
+    Ok(Some(latest))
+}
@@ -0,0 +1,100 @@
+use thiserror::Error;
+
+use crate::{
+    assets::{cache, verify, AssetError},
+    config::{Config, ConfigError},
+    info, warn,
+};
+
+#[derive(Error, Debug)]
+pub enum VerifyError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Asset(#[from] AssetError),
+
+    #[error("{0} asset(s) differ from their archives")]
+    Drift(usize),
+
+    #[error("{0} asset(s) could not be verified")]
+    Unverified(usize),
+}
+
+pub fn exec(id: &Option<String>) -> Result<(), VerifyError> {
+    let config = Config::get()?;
+
+    let ids: Vec<String> = match id {
+        Some(id) => vec![id.clone()],
+        None => config.install_folders.keys().cloned().collect(),
+    };
+
+    let mut drifted_assets = 0;
+    let mut unverified_assets = 0;
+    for id in ids {
+        let Some(install_folders) = config.get_install_folders(&id) else {
+            return Err(AssetError::NotInstalled(id).into());
+        };
+        let Some(asset) = config.get_asset_info(&id) else {
+            return Err(AssetError::NotInstalled(id).into());
+        };
+        let title = &asset.title;
+
+        let drift = match cache::get(&id)? {
+            Some(archive) => verify::verify(archive, asset, install_folders)?,
+            None => match verify::verify_manifest(&id, install_folders)? {
+                Some(drift) => drift,
+                None => {
+                    unverified_assets += 1;
+                    warn!("{id}: {title} cannot be verified, it has no cached archive or install manifest");
+                    continue;
+                }
+            },
+        };
+
+        if drift.is_empty() {
+            info!("{id}: {title} is unmodified");
+            continue;
+        }
+
+        drifted_assets += 1;
+        warn!("{id}: {title} differs from its archive");
+        for file in &drift.modified {
+            warn!("  modified: {file}");
+        }
+        for file in &drift.missing {
+            warn!("  missing:  {file}");
+        }
+        for file in &drift.extra {
+            warn!("  extra:    {file}");
+        }
+    }
+
+    match (drifted_assets, unverified_assets) {
+        (0, 0) => Ok(()),
+        (0, count) => Err(VerifyError::Unverified(count)),
+        (count, _) => Err(VerifyError::Drift(count)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{TestProject, TestResult};
+
+    use super::*;
+
+    #[test]
+    fn drift_is_reported_as_an_error() -> TestResult {
+        let project = TestProject::new()?;
+        project.install(&[
+            ("addons/tool/owned.gd", "1"),
+            ("addons/tool/edited.gd", "1"),
+        ])?;
+
+        exec(&None)?;
+
+        std::fs::write("addons/tool/edited.gd", "mine")?;
+        assert!(matches!(exec(&None), Err(VerifyError::Drift(1))));
+        Ok(())
+    }
+}
@@ -1,16 +1,20 @@
 use std::collections::BTreeMap;
 
 use semver::Version;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use thiserror::Error;
 
 use crate::{
     assets::AssetInfo,
     fs::{
         path::{get_addons_path, get_config_path, get_gitignore_path},
-        ADDONS_GITIGNORE_CONTENT,
+        FsError, ADDONS_GITIGNORE_CONTENT,
+    },
+    godot::{
+        self,
+        asset_library::{self, DEFAULT_REGISTRY_NAME},
+        project::GodotProjectError,
     },
-    godot::{self, project::GodotProjectError},
 };
 
 #[derive(Error, Debug)]
@@ -18,6 +22,9 @@ pub enum ConfigError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error(transparent)]
+    Fs(#[from] FsError),
+
     #[error("Parse error: {0}")]
     Parse(#[from] toml::de::Error),
 
@@ -28,13 +35,64 @@ pub enum ConfigError {
     GodotError(#[from] GodotProjectError),
     #[error("Project is not initialized, try 'godam init'.")]
     Uninitialized,
+    #[error("No registry named '{0}' is configured in godam.toml.")]
+    UnknownRegistry(String),
+}
+
+/// What `godam install` does with assets made for another Godot version than the project's.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compatibility {
+    #[default]
+    Warn,
+    Deny,
+}
+
+impl Compatibility {
+    fn is_default(&self) -> bool {
+        *self == Compatibility::default()
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     pub godot_version: Version,
+    #[serde(default, skip_serializing_if = "Compatibility::is_default")]
+    pub compatibility: Compatibility,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub registries: BTreeMap<String, String>,
+    /// Asset IDs in the order they win conflicts over files several of them install.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub precedence: Vec<String>,
     pub asset_infos: BTreeMap<String, AssetInfo>,
-    pub install_folders: BTreeMap<String, String>,
+    #[serde(deserialize_with = "deserialize_install_folders")]
+    pub install_folders: BTreeMap<String, Vec<String>>,
+}
+
+/// Reads `install_folders`, accepting the single folder per asset older versions wrote.
+fn deserialize_install_folders<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum InstallFolders {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let install_folders = BTreeMap::<String, InstallFolders>::deserialize(deserializer)?;
+    Ok(install_folders
+        .into_iter()
+        .map(|(id, folders)| match folders {
+            InstallFolders::One(folder) => (id, vec![folder]),
+            InstallFolders::Many(folders) => (id, folders),
+        })
+        .collect())
 }
 
 impl Config {
@@ -46,29 +104,89 @@ impl Config {
         Ok(config)
     }
 
+    /// The API base URL of the named registry, or of the default registry if `name` is `None`.
+    pub fn get_registry(&self, name: Option<&str>) -> Result<String, ConfigError> {
+        match name {
+            None | Some(DEFAULT_REGISTRY_NAME) => {
+                Ok(asset_library::get_registry(self.registry.as_deref()))
+            }
+            Some(name) => self
+                .registries
+                .get(name)
+                .map(|url| url.trim_end_matches('/').to_string())
+                .ok_or_else(|| ConfigError::UnknownRegistry(name.to_string())),
+        }
+    }
+
+    /// All registries by name, including the default registry.
+    pub fn get_registries(&self) -> BTreeMap<String, String> {
+        let mut registries: BTreeMap<String, String> = self
+            .registries
+            .iter()
+            .map(|(name, url)| (name.clone(), url.trim_end_matches('/').to_string()))
+            .collect();
+        registries.insert(
+            DEFAULT_REGISTRY_NAME.to_string(),
+            asset_library::get_registry(self.registry.as_deref()),
+        );
+        registries
+    }
+
     pub fn get_asset_info(&self, id: &str) -> Option<&AssetInfo> {
         self.asset_infos.get(id)
     }
 
-    pub fn get_install_folder(&self, asset_id: &str) -> Option<&String> {
+    pub fn get_install_folders(&self, asset_id: &str) -> Option<&Vec<String>> {
         self.install_folders.get(asset_id)
     }
 
-    pub fn set_install_folder(
+    /// The install folders of assets whose editor plugins godam enables.
+    pub fn get_plugin_folders(&self) -> Vec<String> {
+        self.asset_infos
+            .iter()
+            .filter(|(_, asset)| asset.enable)
+            .filter_map(|(id, _)| self.get_install_folders(id))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    pub fn set_install_folders(
         &mut self,
         id: &str,
-        install_folder: String,
+        install_folders: Vec<String>,
     ) -> Result<(), ConfigError> {
-        self.install_folders.insert(id.to_string(), install_folder);
+        self.install_folders.insert(id.to_string(), install_folders);
         self.save()
     }
 
-    pub fn init() -> Result<(), ConfigError> {
-        let version = godot::project::get_version()?;
+    pub fn set_sha256(&mut self, id: &str, sha256: String) -> Result<(), ConfigError> {
+        if let Some(asset) = self.asset_infos.get_mut(id) {
+            asset.sha256 = Some(sha256);
+        }
+        self.save()
+    }
+
+    pub fn set_patches(&mut self, id: &str, patches: Vec<String>) -> Result<(), ConfigError> {
+        if let Some(asset) = self.asset_infos.get_mut(id) {
+            asset.patches = patches;
+        }
+        self.save()
+    }
+
+    pub fn init(godot_version: &Option<Version>) -> Result<(), ConfigError> {
+        let version = match godot_version {
+            Some(version) => version.clone(),
+            None => godot::project::ProjectFile::get()?.get_version()?,
+        };
 
         let config = Config {
             asset_infos: BTreeMap::new(),
             godot_version: version,
+            compatibility: Compatibility::default(),
+            registry: None,
+            registries: BTreeMap::new(),
+            precedence: Vec::new(),
             install_folders: BTreeMap::new(),
         };
 
@@ -92,12 +210,12 @@ impl Config {
     pub fn remove_asset(
         &mut self,
         id: &str,
-    ) -> Result<(Option<AssetInfo>, Option<String>), ConfigError> {
+    ) -> Result<(Option<AssetInfo>, Option<Vec<String>>), ConfigError> {
         let removed_info = self.asset_infos.remove(id);
-        let removed_folder = self.install_folders.remove(id);
+        let removed_folders = self.install_folders.remove(id);
         self.save()?;
 
-        Ok((removed_info, removed_folder))
+        Ok((removed_info, removed_folders))
     }
 
     pub fn save(&self) -> Result<(), ConfigError> {
@@ -8,6 +8,34 @@ pub fn progress_style() -> ProgressStyle {
     ProgressStyle::with_template("{spinner:.cyan:>2} {msg}").unwrap()
 }
 
+/// Lays out the header and rows as columns as wide as their widest cell, header included.
+/// The first column is aligned right, the others left.
+pub fn format_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) -> Vec<String> {
+    let mut widths = header.map(|cell| cell.chars().count());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    std::iter::once(header.map(String::from))
+        .chain(rows.iter().cloned())
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .enumerate()
+                .map(|(column, (cell, width))| match column {
+                    0 => format!("{cell:>width$}"),
+                    _ if column == N - 1 => cell.clone(),
+                    _ => format!("{cell:<width$}"),
+                })
+                .collect();
+            cells.join("  ")
+        })
+        .collect()
+}
+
 #[macro_export]
 macro_rules! info {
     ($($arg:tt)*) => {{
@@ -61,3 +89,31 @@ impl GodamProgressMessage for ProgressBar {
         ));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_fit_the_header_and_every_row() {
+        let rows = [
+            ["1".to_string(), "A".to_string(), "1.0".to_string()],
+            [
+                "1234".to_string(),
+                "Longer title".to_string(),
+                "2.0".to_string(),
+            ],
+        ];
+
+        let lines = format_table(["ID", "Title", "Current"], &rows);
+
+        assert_eq!(
+            lines,
+            [
+                "  ID  Title         Current",
+                "   1  A             1.0",
+                "1234  Longer title  2.0",
+            ]
+        );
+    }
+}
@@ -1,50 +1,103 @@
 //! godam::fs contains wrappers for all filesystem utilities used in the repository
 
-pub const ADDONS_GITIGNORE_CONTENT: &str = "*\n!.gitignore\n!godam.toml\n.godam";
+pub const ADDONS_GITIGNORE_CONTENT: &str = "*\n!.gitignore\n!godam.toml\n!godam.lock\n.godam";
+
+/// The `addons/.gitignore` line that keeps the lock file tracked.
+const GITIGNORE_LOCK_ENTRY: &str = "!godam.lock";
 
 use std::{
     env::current_dir,
-    fs::File,
-    io::{Read, Result, Write},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 
+use thiserror::Error;
+
+type Result<T> = std::result::Result<T, FsError>;
+
+#[derive(Error, Debug)]
+pub enum FsError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Refusing to modify {}, it is outside of the project at {}", .path.display(), .project.display())]
+    PathOutsideProject { path: PathBuf, project: PathBuf },
+}
+
 pub fn safe_remove_dir(path: &Path) -> Result<()> {
-    let asserted_path = get_path_asserted_within_project(path)?;
-    std::fs::remove_dir_all(&asserted_path)
+    let asserted_path = get_path_within_project(path)?;
+    Ok(std::fs::remove_dir_all(asserted_path)?)
 }
 
 pub fn safe_create_dir(path: &Path) -> Result<()> {
-    let asserted_path = get_path_asserted_within_project(path)?;
-    std::fs::create_dir_all(asserted_path)
+    let asserted_path = get_path_within_project(path)?;
+    Ok(std::fs::create_dir_all(asserted_path)?)
 }
 
 pub fn safe_write<C>(path: &Path, contents: C) -> Result<()>
 where
     C: AsRef<[u8]>,
 {
-    let asserted_path = get_path_asserted_within_project(path)?;
-    std::fs::write(asserted_path, contents)
+    let asserted_path = get_path_within_project(path)?;
+    Ok(std::fs::write(asserted_path, contents)?)
 }
 
-pub fn safe_remove_file(path: &Path) -> Result<()> {
-    let asserted_path = get_path_asserted_within_project(path)?;
-    std::fs::remove_file(asserted_path)
+pub fn safe_rename(from: &Path, to: &Path) -> Result<()> {
+    let asserted_from = get_path_within_project(from)?;
+    let asserted_to = get_path_within_project(to)?;
+    Ok(std::fs::rename(asserted_from, asserted_to)?)
 }
 
-pub fn exists(path: &std::path::Path) -> std::io::Result<bool> {
-    std::fs::exists(path)
+/// Removes all empty directories below `path`, and `path` itself if it ends up empty.
+pub fn safe_remove_empty_dirs(path: &Path) -> Result<()> {
+    let asserted_path = get_path_within_project(path)?;
+    for entry in std::fs::read_dir(&asserted_path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            safe_remove_empty_dirs(&entry.path())?;
+        }
+    }
+
+    if std::fs::read_dir(&asserted_path)?.next().is_none() {
+        std::fs::remove_dir(&asserted_path)?;
+    }
+    Ok(())
+}
+
+pub fn safe_remove_file(path: &Path) -> Result<()> {
+    let asserted_path = get_path_within_project(path)?;
+    Ok(std::fs::remove_file(asserted_path)?)
 }
 
-pub fn open(path: &Path) -> Result<File> {
-    File::open(path)
+/// Adds the lock file to `addons/.gitignore`, which only tracked `godam.toml` in projects set
+/// up before the lock file existed.
+pub fn track_lock_in_gitignore() -> Result<()> {
+    let gitignore_path = path::get_gitignore_path();
+    if !exists(gitignore_path)? {
+        return Ok(());
+    }
+
+    let gitignore = read_string(gitignore_path)?;
+    if gitignore
+        .lines()
+        .any(|line| line.trim() == GITIGNORE_LOCK_ENTRY)
+    {
+        return Ok(());
+    }
+
+    let separator = match gitignore.is_empty() || gitignore.ends_with('\n') {
+        true => "",
+        false => "\n",
+    };
+    safe_write(
+        gitignore_path,
+        format!("{gitignore}{separator}{GITIGNORE_LOCK_ENTRY}\n"),
+    )
 }
 
-pub fn create(path: &Path) -> Result<File> {
-    std::fs::File::create(path)
+pub fn exists(path: &std::path::Path) -> std::io::Result<bool> {
+    std::fs::exists(path)
 }
 
-pub fn get_folders_in_directory(path: &Path) -> Result<Vec<String>> {
+pub fn get_folders_in_directory(path: &Path) -> std::io::Result<Vec<String>> {
     let folder_names = std::fs::read_dir(path)?
         .filter_map(|result_dir_entry| {
             result_dir_entry.ok().and_then(|entry| {
@@ -62,44 +115,90 @@ pub fn get_folders_in_directory(path: &Path) -> Result<Vec<String>> {
     Ok(folder_names)
 }
 
-pub fn copy<R, W>(from: &mut R, to: &mut W) -> Result<()>
-where
-    R: ?Sized,
-    W: ?Sized,
-    R: Read,
-    W: Write,
-{
-    std::io::copy(from, to)?;
-    Ok(())
+/// Returns the paths of all files below `path`, relative to `path` and sorted.
+pub fn get_files_in_directory(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut file_paths = Vec::new();
+    let mut directories = vec![PathBuf::new()];
+
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(path.join(&directory))? {
+            let entry = entry?;
+            let relative_path = directory.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                directories.push(relative_path);
+            } else {
+                file_paths.push(relative_path);
+            }
+        }
+    }
+
+    file_paths.sort();
+    Ok(file_paths)
 }
 
-pub fn read_string(path: &Path) -> Result<String> {
+pub fn read_string(path: &Path) -> std::io::Result<String> {
     std::fs::read_to_string(path)
 }
 
-fn get_path_asserted_within_project(path: &Path) -> Result<PathBuf> {
-    let current_dir = std::path::absolute(current_dir()?)?;
-    let target_path = std::path::absolute(path)?;
-    assert!(target_path.starts_with(&current_dir));
-    Ok(target_path)
+pub fn read(path: &Path) -> std::io::Result<Vec<u8>> {
+    std::fs::read(path)
+}
+
+/// Returns the absolute form of `path`, or an error if writing to it would touch anything
+/// outside of the project.
+fn get_path_within_project(path: &Path) -> Result<PathBuf> {
+    let project_path = current_dir()?.canonicalize()?;
+    let absolute_path = std::path::absolute(path)?;
+
+    if !resolve(&absolute_path).starts_with(&project_path) {
+        return Err(FsError::PathOutsideProject {
+            path: absolute_path,
+            project: project_path,
+        });
+    }
+    Ok(absolute_path)
+}
+
+/// Resolves `path` the way the OS does when accessing it, following symlinks as far as the
+/// path exists and applying `..` to where they lead.
+fn resolve(path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => continue,
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+        if let Ok(canonical_path) = resolved.canonicalize() {
+            resolved = canonical_path;
+        }
+    }
+    resolved
 }
 
 pub mod path {
     use std::path::{Path, PathBuf};
 
     const CONFIG_PATH: &str = "./addons/godam.toml";
+    const LOCK_PATH: &str = "./addons/godam.lock";
     const GODOT_PROJECT_FILE_PATH: &str = "./project.godot";
 
     const CACHE_PATH: &str = "./addons/.godam";
+    const PATCHES_PATH: &str = "patches";
 
     const ADDONS_PATH: &str = "./addons";
-    const ADDONS_ZIP_PATTERN: &str = "addons";
     const ADDONS_GITIGNORE_PATH: &str = "./addons/.gitignore";
 
     pub fn get_config_path() -> &'static Path {
         Path::new(CONFIG_PATH)
     }
 
+    pub fn get_lock_path() -> &'static Path {
+        Path::new(LOCK_PATH)
+    }
+
     pub fn get_project_file_path() -> &'static Path {
         Path::new(GODOT_PROJECT_FILE_PATH)
     }
@@ -112,6 +211,38 @@ pub mod path {
         get_cache_path().join(id).with_extension("zip")
     }
 
+    pub fn get_cached_digest_path(id: &str) -> PathBuf {
+        get_cache_path().join(id).with_extension("sha256")
+    }
+
+    pub fn get_manifests_path() -> PathBuf {
+        get_cache_path().join("manifests")
+    }
+
+    pub fn get_manifest_path(id: &str) -> PathBuf {
+        get_manifests_path().join(id).with_extension("toml")
+    }
+
+    pub fn get_staging_path(id: &str) -> PathBuf {
+        get_cache_path().join("staging").join(id)
+    }
+
+    pub fn get_backup_path(id: &str) -> PathBuf {
+        get_cache_path().join("backup").join(id)
+    }
+
+    pub fn get_git_checkout_path(id: &str) -> PathBuf {
+        get_cache_path().join("git").join(id)
+    }
+
+    pub fn get_patches_path() -> &'static Path {
+        Path::new(PATCHES_PATH)
+    }
+
+    pub fn get_patch_path(id: &str) -> PathBuf {
+        get_patches_path().join(id).with_extension("patch")
+    }
+
     pub fn get_addons_path() -> &'static Path {
         Path::new(ADDONS_PATH)
     }
@@ -120,30 +251,46 @@ pub mod path {
         get_addons_path().join(install_folder)
     }
 
-    pub fn get_gitignore_path() -> &'static Path {
-        Path::new(ADDONS_GITIGNORE_PATH)
+    pub fn get_plugin_config_path(install_folder: &str) -> PathBuf {
+        get_install_folder_path(install_folder).join(crate::godot::project::PLUGIN_CONFIG_FILE_NAME)
     }
 
-    pub fn get_out_path_from_archive_path(archive_path: &str) -> Option<PathBuf> {
-        archive_path
-            .find(ADDONS_ZIP_PATTERN)
-            .map(|start| PathBuf::new().join(&archive_path[start..]))
+    pub fn get_gitignore_path() -> &'static Path {
+        Path::new(ADDONS_GITIGNORE_PATH)
     }
 
     #[cfg(test)]
     mod tests {
-        use crate::fs::get_path_asserted_within_project;
+        use crate::{
+            fs::{get_path_within_project, FsError},
+            test_utils::WORKING_DIRECTORY,
+        };
 
         use super::*;
         #[test]
         fn paths_are_within_working_directory() -> Result<(), Box<dyn std::error::Error>> {
+            let _working_directory = WORKING_DIRECTORY.lock();
             let cache_path = get_cache_path();
             let cached_zip_path = get_cached_zip_path("1234");
+            let cached_digest_path = get_cached_digest_path("1234");
 
-            let _ = get_path_asserted_within_project(cache_path)?;
-            let _ = get_path_asserted_within_project(&cached_zip_path)?;
+            let _ = get_path_within_project(cache_path)?;
+            let _ = get_path_within_project(&cached_zip_path)?;
+            let _ = get_path_within_project(&cached_digest_path)?;
 
             Ok(())
         }
+
+        #[test]
+        fn paths_climbing_out_of_working_directory_are_refused() {
+            let _working_directory = WORKING_DIRECTORY.lock();
+            let escaping_path = get_addons_path().join("../../outside");
+
+            assert!(matches!(
+                get_path_within_project(&escaping_path),
+                Err(FsError::PathOutsideProject { .. })
+            ));
+            assert!(get_path_within_project(&get_addons_path().join("foo/../bar")).is_ok());
+        }
     }
 }
@@ -5,7 +5,16 @@ use semver::Version;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::assets::AssetInfo;
+use crate::assets::{checksum, AssetInfo};
+
+/// The public Godot Asset Library, used unless another registry is configured.
+pub const DEFAULT_REGISTRY: &str = "https://godotengine.org/asset-library/api";
+
+/// The name the default registry goes by in `godam.toml` and search results.
+pub const DEFAULT_REGISTRY_NAME: &str = "official";
+
+/// Overrides the configured registry, e.g. to point godam at a mirror.
+pub const REGISTRY_ENV_VAR: &str = "GODAM_REGISTRY";
 
 #[derive(Error, Debug)]
 pub enum AssetLibraryError {
@@ -17,11 +26,6 @@ pub enum AssetLibraryError {
     ParseUrl,
 }
 
-#[derive(Deserialize, Serialize, Clone)]
-pub struct AssetResponse {
-    result: Vec<AssetInfo>,
-}
-
 #[derive(Deserialize, Serialize, Clone)]
 pub struct AssetSearchResponse {
     result: Vec<AssetSearchResult>,
@@ -31,20 +35,43 @@ pub struct AssetSearchResponse {
 pub struct AssetSearchResult {
     pub asset_id: String,
     pub title: String,
+    #[serde(default)]
+    pub author: String,
+    /// The Godot version the release was made for.
+    #[serde(default)]
+    pub godot_version: String,
+    /// The name of the registry the result was found in.
+    #[serde(default)]
+    pub registry: String,
 }
 
 pub struct AssetBlob {
     pub bytes: Vec<u8>,
+    pub sha256: String,
+    /// The revision the source resolved the asset to, e.g. a git commit.
+    pub version: Option<String>,
+}
+
+/// Resolves the registry base URL, preferring the environment over the configured one.
+pub fn get_registry(configured: Option<&str>) -> String {
+    let registry = match std::env::var(REGISTRY_ENV_VAR) {
+        Ok(registry) if !registry.is_empty() => registry,
+        _ => configured.unwrap_or(DEFAULT_REGISTRY).to_string(),
+    };
+
+    registry.trim_end_matches('/').to_string()
 }
 
 pub async fn get_assets_by_name(
+    registry: &str,
     name: &str,
     version: &Version,
 ) -> Result<Vec<AssetSearchResult>, AssetLibraryError> {
-    let version_str = version.to_string();
+    // the library matches releases by major and minor version, like the editor does
+    let version_str = format!("{}.{}", version.major, version.minor);
 
     let url = Url::parse_with_params(
-        "https://godotengine.org/asset-library/api/asset",
+        &format!("{registry}/asset"),
         &[("filter", name), ("godot_version", &version_str)],
     )
     .map_err(|_| AssetLibraryError::ParseUrl)?;
@@ -56,13 +83,13 @@ pub async fn get_assets_by_name(
     Ok(asset_search_response.result)
 }
 
-pub async fn get_asset_by_id(id: &str) -> Result<AssetInfo, AssetLibraryError> {
+pub async fn get_asset_by_id(registry: &str, id: &str) -> Result<AssetInfo, AssetLibraryError> {
     // TODO should validate with param type for ID
     if id.parse::<usize>().is_err() {
         return Err(AssetLibraryError::InvalidId(id.to_string()));
     }
 
-    let request_url = format!("https://godotengine.org/asset-library/api/asset/{id}");
+    let request_url = format!("{registry}/asset/{id}");
     let asset = reqwest::get(&request_url)
         .await?
         .json::<AssetInfo>()
@@ -70,12 +97,77 @@ pub async fn get_asset_by_id(id: &str) -> Result<AssetInfo, AssetLibraryError> {
     Ok(asset)
 }
 
-pub async fn download(asset: &AssetInfo) -> Result<AssetBlob, AssetLibraryError> {
-    let resp = reqwest::get(&asset.download_url).await?;
+pub async fn download(download_url: &str) -> Result<AssetBlob, AssetLibraryError> {
+    let resp = reqwest::get(download_url).await?;
 
     let bytes = resp.bytes().await?;
 
     Ok(AssetBlob {
+        sha256: checksum::sha256(&bytes),
         bytes: bytes.to_vec(),
+        version: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+
+    /// Serves `body` to a single request, returning the registry URL and the requested path.
+    fn serve_once(body: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind local registry");
+        let registry = format!("http://{}/api", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept request");
+            let mut request_line = String::new();
+            BufReader::new(&stream)
+                .read_line(&mut request_line)
+                .expect("read request");
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .expect("write response");
+            request_line
+        });
+
+        (registry, handle)
+    }
+
+    #[tokio::test]
+    async fn asset_is_fetched_from_configured_registry() -> Result<(), Box<dyn std::error::Error>> {
+        let (registry, server) = serve_once(
+            r#"{"title":"LimboAI","download_url":"https://example.com/limboai.zip","version":"12","version_string":"1.2.0"}"#,
+        );
+
+        let asset = get_asset_by_id(&registry, "3228").await?;
+
+        assert!(server.join().unwrap().starts_with("GET /api/asset/3228 "));
+        assert_eq!(asset.title, "LimboAI");
+        assert_eq!(asset.version_string, "1.2.0");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn search_is_sent_to_configured_registry() -> Result<(), Box<dyn std::error::Error>> {
+        let (registry, server) =
+            serve_once(r#"{"result":[{"asset_id":"3228","title":"LimboAI"}]}"#);
+
+        let results = get_assets_by_name(&registry, "limbo", &Version::new(4, 3, 0)).await?;
+
+        assert!(server
+            .join()
+            .unwrap()
+            .starts_with("GET /api/asset?filter=limbo&godot_version=4.3 "));
+        assert_eq!(results[0].asset_id, "3228");
+        Ok(())
+    }
+}
@@ -0,0 +1,726 @@
+//! Godot's ConfigFile format, as used by `project.godot` and `plugin.cfg`
+//!
+//! Files are parsed into sections of typed values, while the original text of every line is
+//! kept, so that writing a file back only changes the values that were set.
+
+use std::fmt::{self, Display, Formatter};
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+#[error("line {line}: {message}")]
+pub struct ConfigFileError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// A value as written by Godot's `VariantWriter`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    StringName(String),
+    NodePath(String),
+    Array(Vec<Value>),
+    Dictionary(Vec<(Value, Value)>),
+    /// A constructor call, such as `PackedStringArray("a", "b")` or `Vector2(0, 1)`.
+    Constructor {
+        name: String,
+        args: Vec<Value>,
+    },
+    /// An `Object(Class,"property":value,...)` entry, as used by input map events.
+    Object {
+        class: String,
+        properties: Vec<(String, Value)>,
+    },
+}
+
+impl Value {
+    /// A string array of the given type, e.g. `PackedStringArray`, holding the strings.
+    pub fn string_array(array_type: &str, strings: &[String]) -> Self {
+        Value::Constructor {
+            name: array_type.to_string(),
+            args: strings.iter().cloned().map(Value::String).collect(),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) | Value::StringName(string) | Value::NodePath(string) => {
+                Some(string)
+            }
+            _ => None,
+        }
+    }
+
+    /// The strings of a packed string array, or of an array holding only strings.
+    pub fn as_string_array(&self) -> Option<Vec<&str>> {
+        let items = match self {
+            Value::Array(items) => items,
+            Value::Constructor { name, args } if name.ends_with("StringArray") => args,
+            _ => return None,
+        };
+        items.iter().map(Value::as_str).collect()
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Nil => write!(f, "null"),
+            Value::Bool(bool) => write!(f, "{bool}"),
+            Value::Int(int) => write!(f, "{int}"),
+            Value::Float(float) if float.is_nan() => write!(f, "nan"),
+            Value::Float(float) if float.is_infinite() => match float.is_sign_positive() {
+                true => write!(f, "inf"),
+                false => write!(f, "inf_neg"),
+            },
+            Value::Float(float) if float.fract() == 0.0 && float.abs() < 1e15 => {
+                write!(f, "{float:.1}")
+            }
+            Value::Float(float) => write!(f, "{float}"),
+            Value::String(string) => write_quoted(f, string),
+            Value::StringName(string) => {
+                write!(f, "&")?;
+                write_quoted(f, string)
+            }
+            Value::NodePath(string) => {
+                write!(f, "^")?;
+                write_quoted(f, string)
+            }
+            Value::Array(items) => {
+                write!(f, "[")?;
+                write_separated(f, items, ", ")?;
+                write!(f, "]")
+            }
+            Value::Dictionary(entries) if entries.is_empty() => write!(f, "{{}}"),
+            Value::Dictionary(entries) => {
+                writeln!(f, "{{")?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(f, ",")?;
+                    }
+                    write!(f, "{key}: {value}")?;
+                }
+                write!(f, "\n}}")
+            }
+            Value::Constructor { name, args } => {
+                write!(f, "{name}(")?;
+                write_separated(f, args, ", ")?;
+                write!(f, ")")
+            }
+            Value::Object { class, properties } => {
+                write!(f, "Object({class}")?;
+                for (key, value) in properties {
+                    write!(f, ",")?;
+                    write_quoted(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+fn write_quoted(f: &mut Formatter<'_>, string: &str) -> fmt::Result {
+    write!(
+        f,
+        "\"{}\"",
+        string.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+fn write_separated(f: &mut Formatter<'_>, values: &[Value], separator: &str) -> fmt::Result {
+    for (index, value) in values.iter().enumerate() {
+        if index > 0 {
+            write!(f, "{separator}")?;
+        }
+        write!(f, "{value}")?;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Line {
+    /// A `key=value` entry, with its original text unless the value was set since.
+    Property {
+        key: String,
+        value: Value,
+        raw: Option<String>,
+    },
+    /// A blank line or comment.
+    Other(String),
+}
+
+impl Line {
+    fn is_blank(&self) -> bool {
+        matches!(self, Line::Other(raw) if raw.trim().is_empty())
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Line::Property { raw: Some(raw), .. } | Line::Other(raw) => write!(f, "{raw}"),
+            Line::Property { key, value, .. } => {
+                if key.contains([' ', '=', '"', ';', '[', ']']) {
+                    write_quoted(f, key)?;
+                } else {
+                    write!(f, "{key}")?;
+                }
+                write!(f, "={value}")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Section {
+    /// Empty for the entries before the first section header.
+    name: String,
+    header: Option<String>,
+    lines: Vec<Line>,
+}
+
+impl Section {
+    fn find_key(&self, key: &str) -> Option<usize> {
+        self.lines.iter().position(
+            |line| matches!(line, Line::Property { key: line_key, .. } if line_key == key),
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigFile {
+    sections: Vec<Section>,
+    final_newline: bool,
+}
+
+impl ConfigFile {
+    pub fn parse(text: &str) -> Result<Self, ConfigFileError> {
+        let mut parser = Parser { text, pos: 0 };
+        let mut sections = vec![Section {
+            name: String::new(),
+            header: None,
+            lines: Vec::new(),
+        }];
+
+        while parser.peek().is_some() {
+            let start = parser.pos;
+            parser.skip_spaces();
+
+            match parser.peek() {
+                None | Some('\n') | Some(';') | Some('#') => {
+                    parser.skip_comment();
+                    let raw = text[start..parser.pos].to_string();
+                    push_line(&mut sections, Line::Other(raw));
+                }
+                Some('[') => {
+                    parser.bump();
+                    let name = parser.take_while(|char| char != ']' && char != '\n');
+                    parser.expect(']')?;
+                    parser.expect_line_end()?;
+                    sections.push(Section {
+                        name: name.trim().to_string(),
+                        header: Some(text[start..parser.pos].to_string()),
+                        lines: Vec::new(),
+                    });
+                }
+                Some(_) => {
+                    let key = match parser.peek() {
+                        Some('"') => parser.parse_string()?,
+                        _ => parser
+                            .take_while(|char| char != '=' && char != '\n')
+                            .trim()
+                            .to_string(),
+                    };
+                    parser.skip_spaces();
+                    parser.expect('=')?;
+                    let value = parser.parse_value()?;
+                    parser.expect_line_end()?;
+                    let raw = Some(text[start..parser.pos].to_string());
+                    push_line(&mut sections, Line::Property { key, value, raw });
+                }
+            }
+            parser.eat('\n');
+        }
+
+        Ok(Self {
+            sections,
+            final_newline: text.is_empty() || text.ends_with('\n'),
+        })
+    }
+
+    fn find_section(&self, section: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.name == section)
+    }
+
+    pub fn has_section(&self, section: &str) -> bool {
+        self.find_section(section).is_some()
+    }
+
+    /// Returns the value of `key` in `section`, where the empty section holds the entries
+    /// before the first section header.
+    pub fn get_value(&self, section: &str, key: &str) -> Option<&Value> {
+        let section = self.find_section(section)?;
+        match &section.lines[section.find_key(key)?] {
+            Line::Property { value, .. } => Some(value),
+            Line::Other(_) => None,
+        }
+    }
+
+    /// Returns the entries of `section` in file order.
+    pub fn get_values(&self, section: &str) -> Vec<(&str, &Value)> {
+        let Some(section) = self.find_section(section) else {
+            return Vec::new();
+        };
+        section
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Property { key, value, .. } => Some((key.as_str(), value)),
+                Line::Other(_) => None,
+            })
+            .collect()
+    }
+
+    /// Sets `key` in `section`, appending the entry or the section if it does not exist yet.
+    pub fn set_value(&mut self, section: &str, key: &str, value: Value) {
+        let line = Line::Property {
+            key: key.to_string(),
+            value,
+            raw: None,
+        };
+
+        let Some(section) = self.sections.iter_mut().find(|s| s.name == section) else {
+            if let Some(last) = self.sections.last_mut() {
+                if last.lines.last().is_some_and(|line| !line.is_blank()) {
+                    last.lines.push(Line::Other(String::new()));
+                }
+            }
+            self.sections.push(Section {
+                name: section.to_string(),
+                header: None,
+                lines: vec![Line::Other(String::new()), line],
+            });
+            return;
+        };
+
+        match section.find_key(key) {
+            Some(index) => section.lines[index] = line,
+            None => {
+                // keep the blank lines that separate sections
+                let mut index = section.lines.len();
+                while index > 0 && section.lines[index - 1].is_blank() {
+                    index -= 1;
+                }
+                section.lines.insert(index, line);
+            }
+        }
+    }
+
+    pub fn remove_value(&mut self, section: &str, key: &str) -> Option<Value> {
+        let section = self.sections.iter_mut().find(|s| s.name == section)?;
+        match section.lines.remove(section.find_key(key)?) {
+            Line::Property { value, .. } => Some(value),
+            Line::Other(_) => None,
+        }
+    }
+
+    /// Removes `section` with all of its lines. When it was the last section, the blank lines
+    /// that separated it from the previous one go too.
+    pub fn remove_section(&mut self, section: &str) {
+        let Some(index) = self.sections.iter().position(|s| s.name == section) else {
+            return;
+        };
+        self.sections.remove(index);
+
+        if index == self.sections.len() {
+            if let Some(last) = self.sections.last_mut() {
+                while last.lines.last().is_some_and(Line::is_blank) {
+                    last.lines.pop();
+                }
+            }
+        }
+    }
+}
+
+impl Display for ConfigFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut lines = Vec::new();
+        for section in &self.sections {
+            match &section.header {
+                Some(header) => lines.push(header.clone()),
+                None if !section.name.is_empty() => lines.push(format!("[{}]", section.name)),
+                None => (),
+            }
+            lines.extend(section.lines.iter().map(Line::to_string));
+        }
+
+        if lines.is_empty() {
+            return Ok(());
+        }
+        write!(f, "{}", lines.join("\n"))?;
+        if self.final_newline {
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn push_line(sections: &mut [Section], line: Line) {
+    if let Some(section) = sections.last_mut() {
+        section.lines.push(line);
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let char = self.peek()?;
+        self.pos += char.len_utf8();
+        Some(char)
+    }
+
+    fn eat(&mut self, char: char) -> bool {
+        let is_next = self.peek() == Some(char);
+        if is_next {
+            self.bump();
+        }
+        is_next
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.bump();
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn error(&self, message: impl Into<String>) -> ConfigFileError {
+        ConfigFileError {
+            line: self.text[..self.pos].matches('\n').count() + 1,
+            message: message.into(),
+        }
+    }
+
+    fn expect(&mut self, char: char) -> Result<(), ConfigFileError> {
+        match self.eat(char) {
+            true => Ok(()),
+            false => Err(self.error(format!("expected '{char}'"))),
+        }
+    }
+
+    /// Skips spaces on the current line, including the `\r` of CRLF line endings.
+    fn skip_spaces(&mut self) {
+        self.take_while(|char| matches!(char, ' ' | '\t' | '\r'));
+    }
+
+    /// Skips whitespace, line breaks included, between the parts of a value.
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn skip_comment(&mut self) {
+        if matches!(self.peek(), Some(';') | Some('#')) {
+            self.take_while(|char| char != '\n');
+        }
+    }
+
+    /// Allows trailing spaces and a comment, up to the end of the line.
+    fn expect_line_end(&mut self) -> Result<(), ConfigFileError> {
+        self.skip_spaces();
+        self.skip_comment();
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some(_) => Err(self.error("unexpected characters after value")),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, ConfigFileError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.parse_string()?)),
+            Some('&') => {
+                self.bump();
+                Ok(Value::StringName(self.parse_string()?))
+            }
+            Some('^') => {
+                self.bump();
+                Ok(Value::NodePath(self.parse_string()?))
+            }
+            Some('[') => {
+                self.bump();
+                Ok(Value::Array(self.parse_list(']', Self::parse_value)?))
+            }
+            Some('{') => {
+                self.bump();
+                let entries = self.parse_list('}', |parser| {
+                    let key = parser.parse_value()?;
+                    parser.skip_whitespace();
+                    parser.expect(':')?;
+                    Ok((key, parser.parse_value()?))
+                })?;
+                Ok(Value::Dictionary(entries))
+            }
+            Some(char) if char.is_ascii_digit() || matches!(char, '-' | '+' | '.') => {
+                self.parse_number()
+            }
+            Some(char) if char.is_alphabetic() || char == '_' => self.parse_identifier_value(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    /// Parses comma separated items up to `close`, allowing a trailing comma.
+    fn parse_list<T>(
+        &mut self,
+        close: char,
+        mut parse_item: impl FnMut(&mut Self) -> Result<T, ConfigFileError>,
+    ) -> Result<Vec<T>, ConfigFileError> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat(close) {
+                return Ok(items);
+            }
+            items.push(parse_item(self)?);
+            self.skip_whitespace();
+            if !self.eat(',') {
+                self.expect(close)?;
+                return Ok(items);
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, ConfigFileError> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(string),
+                Some('\\') => match self.bump() {
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some('r') => string.push('\r'),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some(escape @ ('u' | 'U')) => {
+                        let length = if escape == 'u' { 4 } else { 6 };
+                        let start = self.pos;
+                        for _ in 0..length {
+                            self.bump();
+                        }
+                        let char = u32::from_str_radix(&self.text[start..self.pos], 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.error("invalid unicode escape"))?;
+                        string.push(char);
+                    }
+                    Some(char) => string.push(char),
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some(char) => string.push(char),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Value, ConfigFileError> {
+        let token = self.take_while(|char| char.is_ascii_alphanumeric() || ".+-_".contains(char));
+        if let Ok(int) = token.parse() {
+            return Ok(Value::Int(int));
+        }
+        match token.parse() {
+            Ok(float) => Ok(Value::Float(float)),
+            Err(_) => Err(self.error(format!("invalid number '{token}'"))),
+        }
+    }
+
+    fn parse_identifier(&mut self) -> String {
+        self.take_while(|char| char.is_alphanumeric() || char == '_')
+            .to_string()
+    }
+
+    /// Takes the type parameters of a typed collection, such as `[String, int]` in
+    /// `Dictionary[String, int]({...})`, as written.
+    fn parse_type_parameters(&mut self) -> Result<&'a str, ConfigFileError> {
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            match self.bump() {
+                Some('[') => depth += 1,
+                Some(']') if depth == 1 => return Ok(&self.text[start..self.pos]),
+                Some(']') => depth -= 1,
+                Some(_) => (),
+                None => return Err(self.error("unterminated type parameters")),
+            }
+        }
+    }
+
+    fn parse_identifier_value(&mut self) -> Result<Value, ConfigFileError> {
+        let mut name = self.parse_identifier();
+        // typed collections keep their type parameters as part of the constructor name
+        if self.peek() == Some('[') {
+            name.push_str(self.parse_type_parameters()?);
+            self.expect('(')?;
+            let args = self.parse_list(')', Self::parse_value)?;
+            return Ok(Value::Constructor { name, args });
+        }
+        if self.eat('(') {
+            if name != "Object" {
+                let args = self.parse_list(')', Self::parse_value)?;
+                return Ok(Value::Constructor { name, args });
+            }
+
+            self.skip_whitespace();
+            let class = self.parse_identifier();
+            let mut properties = Vec::new();
+            loop {
+                self.skip_whitespace();
+                if self.eat(')') {
+                    return Ok(Value::Object { class, properties });
+                }
+                self.expect(',')?;
+                self.skip_whitespace();
+                let key = self.parse_string()?;
+                self.skip_whitespace();
+                self.expect(':')?;
+                properties.push((key, self.parse_value()?));
+            }
+        }
+
+        match name.as_str() {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            "null" | "nil" => Ok(Value::Nil),
+            "inf" => Ok(Value::Float(f64::INFINITY)),
+            "inf_neg" => Ok(Value::Float(f64::NEG_INFINITY)),
+            "nan" => Ok(Value::Float(f64::NAN)),
+            _ => Err(self.error(format!("unknown identifier '{name}'"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT: &str = r#"; Engine configuration file.
+; It's best edited using the editor UI and not directly,
+
+config_version=5
+
+[application]
+
+config/name="Game \"Deluxe\""
+config/features=PackedStringArray("4.3", "C#", "Forward Plus")
+config/icon="res://icon.svg"
+
+[autoload]
+
+Events="*res://autoload/events.gd"
+
+[display]
+
+window/size/viewport_width=1280
+window/stretch/scale=1.5
+
+[input]
+
+jump={
+"deadzone": 0.5,
+"events": [Object(InputEventKey,"resource_local_to_scene":false,"keycode":0,"physical_keycode":32,"unicode":32,"echo":false,"script":null)
+]
+}
+
+[rendering]
+
+environment/defaults/default_clear_color=Color(0.1, 0.1, 0.1, 1)
+
+[typed]
+
+names=Array[StringName]([&"jump", &"run"])
+costs=Dictionary[String, Array[int]]({
+"sword": [1, 2]
+})
+"#;
+
+    #[test]
+    fn files_are_written_back_unchanged() -> Result<(), ConfigFileError> {
+        let config = ConfigFile::parse(PROJECT)?;
+
+        assert_eq!(config.to_string(), PROJECT);
+        assert_eq!(config.get_value("", "config_version"), Some(&Value::Int(5)));
+        assert_eq!(
+            config
+                .get_value("application", "config/name")
+                .and_then(Value::as_str),
+            Some("Game \"Deluxe\"")
+        );
+        assert_eq!(
+            config
+                .get_value("application", "config/features")
+                .and_then(Value::as_string_array),
+            Some(vec!["4.3", "C#", "Forward Plus"])
+        );
+        assert_eq!(
+            config.get_value("display", "window/stretch/scale"),
+            Some(&Value::Float(1.5))
+        );
+        assert!(matches!(
+            config.get_value("input", "jump"),
+            Some(Value::Dictionary(entries)) if entries.len() == 2
+        ));
+        assert!(matches!(
+            config.get_value("typed", "names"),
+            Some(Value::Constructor { name, args })
+                if name == "Array[StringName]" && matches!(args.as_slice(), [Value::Array(names)] if names.len() == 2)
+        ));
+        assert!(matches!(
+            config.get_value("typed", "costs"),
+            Some(Value::Constructor { name, args })
+                if name == "Dictionary[String, Array[int]]" && matches!(args.as_slice(), [Value::Dictionary(_)])
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_values_are_the_only_lines_that_change() -> Result<(), ConfigFileError> {
+        let mut config = ConfigFile::parse(PROJECT)?;
+
+        config.set_value("display", "window/stretch/scale", Value::Float(2.0));
+        config.set_value(
+            "autoload",
+            "Save",
+            Value::String("*res://save.gd".to_string()),
+        );
+
+        let expected = PROJECT.replace("scale=1.5", "scale=2.0").replace(
+            "autoload/events.gd\"\n",
+            "autoload/events.gd\"\nSave=\"*res://save.gd\"\n",
+        );
+        assert_eq!(config.to_string(), expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_values_are_reported_with_their_line() {
+        let error = ConfigFile::parse("[application]\n\nconfig/name=\"Game\n").unwrap_err();
+
+        assert_eq!(error.line, 4);
+    }
+}
@@ -1,2 +1,4 @@
 pub mod asset_library;
+pub mod config_file;
+pub mod plugin;
 pub mod project;
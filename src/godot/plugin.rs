@@ -0,0 +1,48 @@
+use thiserror::Error;
+
+use crate::fs::path::get_plugin_config_path;
+
+use super::config_file::{ConfigFile, ConfigFileError, Value};
+
+const PLUGIN_SECTION: &str = "plugin";
+
+#[derive(Error, Debug)]
+pub enum PluginConfigError {
+    #[error("Could not read plugin.cfg file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse plugin.cfg file: {0}")]
+    Parse(#[from] ConfigFileError),
+}
+
+/// The `[plugin]` section of an editor plugin's `plugin.cfg`.
+#[derive(Debug, PartialEq)]
+pub struct PluginConfig {
+    pub name: String,
+    pub author: String,
+    pub version: String,
+}
+
+impl PluginConfig {
+    /// Returns the plugin config of an addon folder, or `None` if it is not an editor plugin.
+    pub fn get(install_folder: &str) -> Result<Option<Self>, PluginConfigError> {
+        let path = get_plugin_config_path(install_folder);
+        if !crate::fs::exists(&path)? {
+            return Ok(None);
+        }
+
+        let config = ConfigFile::parse(&crate::fs::read_string(&path)?)?;
+        let get = |key: &str| {
+            config
+                .get_value(PLUGIN_SECTION, key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        Ok(Some(Self {
+            name: get("name"),
+            author: get("author"),
+            version: get("version"),
+        }))
+    }
+}
@@ -1,40 +1,369 @@
 use semver::Version;
-use std::str::FromStr;
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
 use thiserror::Error;
 
-use crate::fs::path::get_project_file_path;
+use crate::fs::{
+    path::{get_plugin_config_path, get_project_file_path},
+    FsError,
+};
 
-const GODOT_PROJECT_LINE_START: &str = "config/features=PackedStringArray(";
+use super::config_file::{ConfigFile, ConfigFileError, Value};
+
+const CONFIG_VERSION_KEY: &str = "config_version";
+const APPLICATION_SECTION: &str = "application";
+const FEATURES_KEY: &str = "config/features";
+const AUTOLOAD_SECTION: &str = "autoload";
+const DOTNET_SECTION: &str = "dotnet";
+const MONO_SECTION: &str = "mono";
+const CSHARP_FEATURE: &str = "C#";
+const EDITOR_PLUGINS_SECTION: &str = "editor_plugins";
+const ENABLED_PLUGINS_KEY: &str = "enabled";
+pub const PLUGIN_CONFIG_FILE_NAME: &str = "plugin.cfg";
+const RESOURCE_PATH_PREFIX: &str = "res://";
 
 #[derive(Error, Debug)]
 pub enum GodotProjectError {
     #[error("Could not find project.godot file in working directory.")]
     ProjectNotFound,
+    #[error("Could not parse project.godot file: {0}")]
+    Parse(#[from] ConfigFileError),
+    #[error("Could not find the engine version in project.godot file.")]
+    VersionNotFound,
     #[error("Could not parse version from project.godot file.")]
     VersionParse(#[from] semver::Error),
+    #[error("Could not write project.godot file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not write project.godot file: {0}")]
+    Fs(#[from] FsError),
+}
+
+/// An autoloaded script or scene.
+#[derive(Debug, PartialEq)]
+pub struct Autoload {
+    pub name: String,
+    pub path: String,
+    /// Whether the autoload is registered as a global singleton.
+    pub is_singleton: bool,
 }
 
-pub fn get_version() -> Result<Version, GodotProjectError> {
-    let file = crate::fs::read_string(get_project_file_path())
-        .map_err(|_| GodotProjectError::ProjectNotFound)?;
-    let string = file
-        .lines()
-        .find(|line| line.starts_with(GODOT_PROJECT_LINE_START));
-
-    match string {
-        Some(line) => {
-            let first_quote = line.find('"').expect("first quote exists");
-            let second_quote = first_quote
-                + line[first_quote + 1..]
-                    .find('"')
-                    .expect("matching closing quote");
-            let mut version_str = line[first_quote + 1..=second_quote].to_string();
-            if version_str.len() == 3 {
-                version_str += ".0";
+/// The `project.godot` file. Writing it back only changes the values godam set.
+pub struct ProjectFile {
+    config: ConfigFile,
+}
+
+impl ProjectFile {
+    pub fn get() -> Result<Self, GodotProjectError> {
+        let string = crate::fs::read_string(get_project_file_path())
+            .map_err(|_| GodotProjectError::ProjectNotFound)?;
+        Self::parse(&string)
+    }
+
+    pub fn parse(string: &str) -> Result<Self, GodotProjectError> {
+        Ok(Self {
+            config: ConfigFile::parse(string)?,
+        })
+    }
+
+    pub fn save(&self) -> Result<(), GodotProjectError> {
+        Ok(crate::fs::safe_write(
+            get_project_file_path(),
+            self.to_string(),
+        )?)
+    }
+
+    fn get_features(&self) -> Vec<&str> {
+        self.config
+            .get_value(APPLICATION_SECTION, FEATURES_KEY)
+            .and_then(Value::as_string_array)
+            .unwrap_or_default()
+    }
+
+    /// The engine version the project was last saved with.
+    ///
+    /// Godot 4 lists it in the feature tags. Godot 3 only records the format of the file in
+    /// `config_version`, so those projects are assumed to target the oldest release writing that
+    /// format: 3.0 for `config_version=3` and 3.1 for `config_version=4`. `godam init --godot`
+    /// sets the exact version instead.
+    pub fn get_version(&self) -> Result<Version, GodotProjectError> {
+        let feature_version = self.get_features().into_iter().find(|feature| {
+            feature.contains('.') && feature.chars().all(|c| c.is_ascii_digit() || c == '.')
+        });
+
+        if let Some(version) = feature_version {
+            return Ok(parse_version(version)?);
+        }
+
+        match self.config.get_value("", CONFIG_VERSION_KEY) {
+            Some(Value::Int(5)) => Ok(Version::new(4, 0, 0)),
+            Some(Value::Int(4)) => Ok(Version::new(3, 1, 0)),
+            Some(Value::Int(3)) => Ok(Version::new(3, 0, 0)),
+            _ => Err(GodotProjectError::VersionNotFound),
+        }
+    }
+
+    /// Whether the project uses C#, which Godot 4 lists in the feature tags and Godot 3 marks
+    /// with a `[mono]` section.
+    #[allow(dead_code)] // read-only accessor, not used by any command yet
+    pub fn is_csharp(&self) -> bool {
+        self.get_features().contains(&CSHARP_FEATURE)
+            || self.config.has_section(DOTNET_SECTION)
+            || self.config.has_section(MONO_SECTION)
+    }
+
+    #[allow(dead_code)] // read-only accessor, not used by any command yet
+    pub fn get_autoloads(&self) -> Vec<Autoload> {
+        self.config
+            .get_values(AUTOLOAD_SECTION)
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let path = value.as_str()?;
+                Some(Autoload {
+                    name: name.to_string(),
+                    path: path.trim_start_matches('*').to_string(),
+                    is_singleton: path.starts_with('*'),
+                })
+            })
+            .collect()
+    }
+
+    /// The `res://` paths of the `plugin.cfg` files enabled in the editor.
+    pub fn get_enabled_plugins(&self) -> Vec<String> {
+        self.config
+            .get_value(EDITOR_PLUGINS_SECTION, ENABLED_PLUGINS_KEY)
+            .and_then(Value::as_string_array)
+            .map(|plugins| plugins.into_iter().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn set_enabled_plugins(&mut self, plugins: &[String]) {
+        match plugins.is_empty() {
+            true => {
+                self.config
+                    .remove_value(EDITOR_PLUGINS_SECTION, ENABLED_PLUGINS_KEY);
+                if self.config.get_values(EDITOR_PLUGINS_SECTION).is_empty() {
+                    self.config.remove_section(EDITOR_PLUGINS_SECTION);
+                }
+            }
+            false => {
+                // Godot 3 calls packed arrays pool arrays
+                let array_type = match self.get_version() {
+                    Ok(version) if version.major < 4 => "PoolStringArray",
+                    _ => "PackedStringArray",
+                };
+                self.config.set_value(
+                    EDITOR_PLUGINS_SECTION,
+                    ENABLED_PLUGINS_KEY,
+                    Value::string_array(array_type, plugins),
+                )
             }
-            let version = Version::from_str(&version_str)?;
-            Ok(version)
         }
-        None => Err(GodotProjectError::ProjectNotFound),
+    }
+}
+
+impl Display for ProjectFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.config)
+    }
+}
+
+/// Parses a Godot version, which usually leaves out the patch version, e.g. `4.3`.
+pub fn parse_version(version: &str) -> Result<Version, semver::Error> {
+    let mut version = version.to_string();
+    if version.matches('.').count() == 1 {
+        version += ".0";
+    }
+    Version::from_str(&version)
+}
+
+fn get_plugin_resource_path(install_folder: &str) -> String {
+    format!("{RESOURCE_PATH_PREFIX}addons/{install_folder}/{PLUGIN_CONFIG_FILE_NAME}")
+}
+
+/// Enables the editor plugins of the install folders that contain a `plugin.cfg`.
+///
+/// `project.godot` is left alone when there is nothing to enable.
+pub fn enable_plugins(install_folders: &[String]) -> Result<(), GodotProjectError> {
+    let is_plugin = |install_folder: &String| {
+        crate::fs::exists(&get_plugin_config_path(install_folder)).unwrap_or(false)
+    };
+    if !install_folders.iter().any(is_plugin) {
+        return Ok(());
+    }
+
+    let mut project = ProjectFile::get()?;
+    let mut plugins = project.get_enabled_plugins();
+    let enabled_count = plugins.len();
+
+    for install_folder in install_folders {
+        let plugin = get_plugin_resource_path(install_folder);
+        if !plugins.contains(&plugin) && crate::fs::exists(&get_plugin_config_path(install_folder))?
+        {
+            plugins.push(plugin);
+        }
+    }
+
+    if plugins.len() != enabled_count {
+        project.set_enabled_plugins(&plugins);
+        project.save()?;
+    }
+    Ok(())
+}
+
+/// Disables the editor plugins of the install folders.
+pub fn disable_plugins(install_folders: &[String]) -> Result<(), GodotProjectError> {
+    if install_folders.is_empty() {
+        return Ok(());
+    }
+
+    let mut project = ProjectFile::get()?;
+    let mut plugins = project.get_enabled_plugins();
+    let enabled_count = plugins.len();
+
+    let disabled: Vec<String> = install_folders
+        .iter()
+        .map(|install_folder| get_plugin_resource_path(install_folder))
+        .collect();
+    plugins.retain(|plugin| !disabled.contains(plugin));
+
+    if plugins.len() != enabled_count {
+        project.set_enabled_plugins(&plugins);
+        project.save()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugins_are_enabled_without_touching_other_lines() -> Result<(), GodotProjectError> {
+        let original = "config_version=5\n\n[application]\n\nconfig/name=\"Game\"\nconfig/features=PackedStringArray(\"4.3\", \"Forward Plus\")\n\n[rendering]\n\nrenderer/rendering_method=\"mobile\"\n";
+        let mut project = ProjectFile::parse(original)?;
+
+        assert_eq!(project.get_version()?, Version::new(4, 3, 0));
+
+        project.set_enabled_plugins(&["res://addons/a/plugin.cfg".to_string()]);
+        project.set_enabled_plugins(&[
+            "res://addons/a/plugin.cfg".to_string(),
+            "res://addons/b/plugin.cfg".to_string(),
+        ]);
+
+        assert_eq!(
+            project.to_string(),
+            format!("{original}\n[editor_plugins]\n\nenabled=PackedStringArray(\"res://addons/a/plugin.cfg\", \"res://addons/b/plugin.cfg\")\n")
+        );
+        assert_eq!(project.get_enabled_plugins().len(), 2);
+
+        project.set_enabled_plugins(&[]);
+        assert!(project.get_enabled_plugins().is_empty());
+        assert_eq!(project.to_string(), original);
+
+        let mut project = ProjectFile::parse("config_version=5\n\n[editor_plugins]\n\nenabled=PackedStringArray(\"res://addons/a/plugin.cfg\")\n\n[rendering]\n\nrenderer/rendering_method=\"mobile\"\n")?;
+        project.set_enabled_plugins(&[]);
+        assert_eq!(
+            project.to_string(),
+            "config_version=5\n\n[rendering]\n\nrenderer/rendering_method=\"mobile\"\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn csharp_and_autoloads_are_read() -> Result<(), GodotProjectError> {
+        let project = ProjectFile::parse(
+            r#"; Engine configuration file.
+; It's best edited using the editor UI and not directly,
+; since the parameters that go here are not all obvious.
+;
+; Param=Value
+
+config_version=5
+
+[application]
+
+config/name="Game"
+run/main_scene="res://main.tscn"
+config/features=PackedStringArray("4.3", "C#", "Forward Plus")
+config/icon="res://icon.svg"
+
+[autoload]
+
+Events="*res://autoload/events.gd"
+SaveData="res://autoload/save_data.tscn"
+
+[dotnet]
+
+project/assembly_name="Game"
+"#,
+        )?;
+
+        assert!(project.is_csharp());
+        assert_eq!(
+            project.get_autoloads(),
+            vec![
+                Autoload {
+                    name: "Events".to_string(),
+                    path: "res://autoload/events.gd".to_string(),
+                    is_singleton: true,
+                },
+                Autoload {
+                    name: "SaveData".to_string(),
+                    path: "res://autoload/save_data.tscn".to_string(),
+                    is_singleton: false,
+                },
+            ]
+        );
+        assert!(!ProjectFile::parse("config_version=5\n\n[application]\n\nconfig/features=PackedStringArray(\"4.3\", \"Forward Plus\")\n")?.is_csharp());
+        Ok(())
+    }
+
+    #[test]
+    fn godot_3_projects_are_recognized() -> Result<(), GodotProjectError> {
+        let mut project = ProjectFile::parse(
+            r#"; Engine configuration file.
+; It's best edited using the editor UI and not directly,
+; since the parameters that go here are not all obvious.
+;
+; Param=Value
+
+config_version=4
+
+_global_script_classes=[  ]
+_global_script_class_icons={
+}
+
+[application]
+
+config/name="Legacy"
+run/main_scene="res://Main.tscn"
+config/icon="res://icon.png"
+
+[mono]
+
+project/assembly_name="Legacy"
+
+[rendering]
+
+environment/default_environment="res://default_env.tres"
+"#,
+        )?;
+
+        project.set_enabled_plugins(&["res://addons/a/plugin.cfg".to_string()]);
+
+        assert_eq!(project.get_version()?, Version::new(3, 1, 0));
+        assert!(project.is_csharp());
+        assert_eq!(
+            ProjectFile::parse("config_version=3\n\n[application]\n\nconfig/name=\"Legacy\"\n")?
+                .get_version()?,
+            Version::new(3, 0, 0)
+        );
+        assert!(project
+            .to_string()
+            .ends_with("enabled=PoolStringArray(\"res://addons/a/plugin.cfg\")\n"));
+        Ok(())
     }
 }
@@ -4,6 +4,10 @@ mod config;
 mod console;
 mod fs;
 mod godot;
+mod lock;
+mod sources;
+#[cfg(test)]
+mod test_utils;
 mod traits;
 
 use clap::Parser;
@@ -22,10 +26,22 @@ pub struct Cli {
 
 pub async fn run(command: &Command) -> Result<(), Box<dyn std::error::Error>> {
     match command {
-        Command::Init => init::exec()?,
+        Command::Init { godot } => init::exec(godot)?,
         Command::Search { name } => search::exec(name).await?,
-        Command::Install { name } => install::exec(name).await?,
+        Command::Install {
+            name,
+            registry,
+            force,
+            reinstall,
+        } => install::exec(name, registry, *force, *reinstall).await?,
+        Command::Adopt { registry } => adopt::exec(registry).await?,
         Command::Uninstall { name } => uninstall::exec(name)?,
+        Command::Update { name, force } => update::exec(name, *force).await?,
+        Command::Outdated => outdated::exec().await?,
+        Command::Migrate { godot, reinstall } => migrate::exec(godot, *reinstall).await?,
+        Command::Verify { name } => verify::exec(name)?,
+        Command::Diff { name } => diff::exec(name).await?,
+        Command::Patch { name } => patch::exec(name).await?,
         Command::List => list::exec()?,
         Command::Clean => clean::exec()?,
     };
@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    assets::AssetInfo,
+    fs::{path::get_lock_path, FsError},
+};
+
+const LOCK_FILE_HEADER: &str =
+    "# This file is automatically generated by godam.\n# It is not intended for manual editing.\n";
+
+#[derive(Error, Debug)]
+pub enum LockError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Fs(#[from] FsError),
+
+    #[error("Parse error: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("Parse error: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+/// The resolved state of an asset at the time it was first installed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LockedAsset {
+    pub download_url: String,
+    pub version: String,
+    pub version_string: String,
+    pub modify_date: String,
+    pub sha256: String,
+    /// The branch, tag or commit the git origin asked for when the commit was pinned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+}
+
+impl LockedAsset {
+    pub fn new(asset: &AssetInfo, sha256: String, resolved_version: Option<String>) -> Self {
+        Self {
+            download_url: asset.source_url().to_string(),
+            version: resolved_version.unwrap_or_else(|| asset.version.clone()),
+            version_string: asset.version_string.clone(),
+            modify_date: asset.modify_date.clone(),
+            sha256,
+            git_ref: asset.git_ref(),
+        }
+    }
+
+    /// Whether the entry still pins `asset`, i.e. its git origin asks for the same branch, tag
+    /// or commit as when it was pinned.
+    pub fn pins(&self, asset: &AssetInfo) -> bool {
+        self.git_ref == asset.git_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Lock {
+    #[serde(default)]
+    pub assets: BTreeMap<String, LockedAsset>,
+}
+
+impl Lock {
+    pub fn get() -> Result<Self, LockError> {
+        let lock_path = get_lock_path();
+        if !crate::fs::exists(lock_path)? {
+            return Ok(Lock::default());
+        }
+
+        let string = crate::fs::read_string(lock_path)?;
+        let lock = toml::from_str(&string)?;
+
+        Ok(lock)
+    }
+
+    pub fn get_locked_asset(&self, id: &str) -> Option<&LockedAsset> {
+        self.assets.get(id)
+    }
+
+    pub fn lock_asset(&mut self, id: &str, locked: LockedAsset) -> Result<(), LockError> {
+        self.assets.insert(id.to_string(), locked);
+        self.save()
+    }
+
+    pub fn unlock_asset(&mut self, id: &str) -> Result<Option<LockedAsset>, LockError> {
+        let removed = self.assets.remove(id);
+        self.save()?;
+
+        Ok(removed)
+    }
+
+    pub fn save(&self) -> Result<(), LockError> {
+        let lock_path = get_lock_path();
+        let str = format!("{LOCK_FILE_HEADER}\n{}", toml::to_string(self)?);
+        crate::fs::safe_write(lock_path, str)?;
+
+        Ok(crate::fs::track_lock_in_gitignore()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_stop_pinning_when_the_git_ref_changes() -> Result<(), toml::de::Error> {
+        let tagged: AssetInfo = toml::from_str(
+            "title = \"Fork\"\norigin = { git = \"https://example.com/fork.git\", tag = \"v1.0\" }",
+        )?;
+        let retagged: AssetInfo = toml::from_str(
+            "title = \"Fork\"\norigin = { git = \"https://example.com/fork.git\", tag = \"v2.0\" }",
+        )?;
+        let untagged: AssetInfo = toml::from_str(
+            "title = \"Fork\"\norigin = { git = \"https://example.com/fork.git\" }",
+        )?;
+
+        let locked = LockedAsset::new(&tagged, "sha256".to_string(), Some("abc".to_string()));
+
+        assert!(locked.pins(&tagged));
+        assert!(!locked.pins(&retagged));
+        assert!(!locked.pins(&untagged));
+        Ok(())
+    }
+}
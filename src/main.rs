@@ -1,11 +1,17 @@
+use std::process::ExitCode;
+
 use clap::Parser;
 use godam::Cli;
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
 
-    if let Err(e) = godam::run(&cli.command).await {
-        eprintln!("godam: {e}");
+    match godam::run(&cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("godam: {e}");
+            ExitCode::FAILURE
+        }
     }
 }
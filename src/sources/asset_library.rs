@@ -0,0 +1,14 @@
+use crate::godot::asset_library::{self, AssetBlob};
+
+use super::{AssetSource, SourceError};
+
+/// An asset published on the Godot Asset Library.
+pub struct AssetLibrarySource<'a> {
+    pub download_url: &'a str,
+}
+
+impl AssetSource for AssetLibrarySource<'_> {
+    async fn fetch(&self) -> Result<AssetBlob, SourceError> {
+        Ok(asset_library::download(self.download_url).await?)
+    }
+}
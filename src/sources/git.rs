@@ -0,0 +1,95 @@
+use std::path::Path;
+
+use tokio::process::Command;
+
+use crate::{fs::path::get_git_checkout_path, godot::asset_library::AssetBlob, warn};
+
+use super::{zip_directory, AssetSource, SourceError};
+
+/// A git repository, optionally pinned to a branch, tag or commit.
+pub struct GitSource<'a> {
+    pub id: &'a str,
+    pub url: &'a str,
+    pub branch: Option<&'a str>,
+    pub tag: Option<&'a str>,
+    pub rev: Option<&'a str>,
+}
+
+impl AssetSource for GitSource<'_> {
+    async fn fetch(&self) -> Result<AssetBlob, SourceError> {
+        let checkout_path = get_git_checkout_path(self.id);
+        if crate::fs::exists(&checkout_path)? {
+            crate::fs::safe_remove_dir(&checkout_path)?;
+        }
+
+        let result = self.checkout(&checkout_path).await;
+        let blob = match result {
+            Ok(version) => zip_directory(&checkout_path).map(|blob| AssetBlob {
+                version: Some(version),
+                ..blob
+            }),
+            Err(e) => Err(e),
+        };
+
+        // a failed cleanup must not hide why the checkout failed
+        let cleanup = match crate::fs::exists(&checkout_path) {
+            Ok(true) => crate::fs::safe_remove_dir(&checkout_path),
+            Ok(false) => Ok(()),
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = cleanup {
+            warn!("Could not remove checkout {}: {e}", checkout_path.display());
+        }
+        blob
+    }
+}
+
+impl GitSource<'_> {
+    /// Clones the repository into `checkout_path`, returning the checked out commit.
+    async fn checkout(&self, checkout_path: &Path) -> Result<String, SourceError> {
+        let mut clone = Command::new("git");
+        clone.arg("clone").arg("--quiet");
+        if self.rev.is_none() {
+            clone.arg("--depth").arg("1");
+            if let Some(reference) = self.branch.or(self.tag) {
+                clone.arg("--branch").arg(reference);
+            }
+        }
+        clone.arg(self.url).arg(checkout_path);
+        run(&mut clone).await?;
+
+        if let Some(rev) = self.rev {
+            run(Command::new("git")
+                .arg("-C")
+                .arg(checkout_path)
+                .arg("checkout")
+                .arg("--quiet")
+                .arg(rev))
+            .await?;
+        }
+
+        let commit = run(Command::new("git")
+            .arg("-C")
+            .arg(checkout_path)
+            .arg("rev-parse")
+            .arg("HEAD"))
+        .await?;
+
+        Ok(commit.trim().to_string())
+    }
+}
+
+async fn run(command: &mut Command) -> Result<String, SourceError> {
+    let output = command
+        .output()
+        .await
+        .map_err(|e| SourceError::Git(format!("could not run git: {e}")))?;
+
+    if !output.status.success() {
+        return Err(SourceError::Git(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
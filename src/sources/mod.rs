@@ -0,0 +1,164 @@
+//! Asset sources godam can fetch asset archives from
+
+pub mod asset_library;
+pub mod git;
+pub mod path;
+pub mod url;
+
+use std::{
+    future::Future,
+    io::{Cursor, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zip::{write::SimpleFileOptions, DateTime, ZipWriter};
+
+use crate::{
+    assets::{checksum, AssetInfo},
+    fs::FsError,
+    godot::asset_library::{AssetBlob, AssetLibraryError},
+    lock::LockedAsset,
+};
+
+use self::{asset_library::AssetLibrarySource, git::GitSource, path::PathSource, url::UrlSource};
+
+#[derive(Error, Debug)]
+pub enum SourceError {
+    #[error(transparent)]
+    Request(#[from] AssetLibraryError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Fs(#[from] FsError),
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    #[error("Git error: {0}")]
+    Git(String),
+}
+
+/// Where an asset is fetched from, when it does not come from the Asset Library.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum AssetOrigin {
+    Git {
+        git: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        branch: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tag: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rev: Option<String>,
+    },
+    Url {
+        url: String,
+    },
+    Path {
+        path: String,
+    },
+}
+
+pub trait AssetSource {
+    /// Fetches the asset archive from the source.
+    fn fetch(&self) -> impl Future<Output = Result<AssetBlob, SourceError>> + Send;
+}
+
+/// Fetches the archive of `asset` from its configured source, honoring the pinned lock entry if present.
+pub async fn fetch(
+    id: &str,
+    asset: &AssetInfo,
+    locked: Option<&LockedAsset>,
+) -> Result<AssetBlob, SourceError> {
+    match &asset.origin {
+        None => {
+            let download_url = match locked {
+                Some(locked) => &locked.download_url,
+                None => &asset.download_url,
+            };
+            AssetLibrarySource { download_url }.fetch().await
+        }
+        Some(AssetOrigin::Git {
+            git,
+            branch,
+            tag,
+            rev,
+        }) => {
+            let source = GitSource {
+                id,
+                url: git,
+                branch: branch.as_deref(),
+                tag: tag.as_deref(),
+                rev: locked
+                    .filter(|locked| locked.pins(asset))
+                    .map(|locked| locked.version.as_str())
+                    .filter(|version| !version.is_empty())
+                    .or(rev.as_deref()),
+            };
+            source.fetch().await
+        }
+        Some(AssetOrigin::Url { url }) => UrlSource { url }.fetch().await,
+        Some(AssetOrigin::Path { path }) => PathSource { path }.fetch().await,
+    }
+}
+
+/// Packs every file below `root` into an in-memory zip archive.
+///
+/// Entries are sorted and timestamps are zeroed, so the same files always produce the same digest.
+pub fn zip_directory(root: &Path) -> Result<AssetBlob, SourceError> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().last_modified_time(DateTime::default());
+
+    for file_path in crate::fs::get_files_in_directory(root)? {
+        if file_path.starts_with(".git") {
+            continue;
+        }
+
+        let name = file_path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        writer.start_file(name, options)?;
+        writer.write_all(&crate::fs::read(&root.join(&file_path))?)?;
+    }
+
+    let bytes = writer.finish()?.into_inner();
+
+    Ok(AssetBlob {
+        sha256: checksum::sha256(&bytes),
+        bytes,
+        version: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn origins_are_parsed_from_config_entries() -> Result<(), Box<dyn std::error::Error>> {
+        let git: AssetInfo = toml::from_str(
+            "title = \"Fork\"\norigin = { git = \"https://example.com/fork.git\", tag = \"v1.0\" }",
+        )?;
+        let url: AssetInfo = toml::from_str(
+            "title = \"Zip\"\norigin = { url = \"https://example.com/addon.zip\" }",
+        )?;
+        let path: AssetInfo =
+            toml::from_str("title = \"Local\"\norigin = { path = \"../addon\" }")?;
+
+        assert_eq!(
+            git.origin,
+            Some(AssetOrigin::Git {
+                git: "https://example.com/fork.git".to_string(),
+                branch: None,
+                tag: Some("v1.0".to_string()),
+                rev: None,
+            })
+        );
+        assert_eq!(url.source_url(), "https://example.com/addon.zip");
+        assert!(!path.is_cacheable());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use std::path::Path;
+
+use crate::{assets::checksum, godot::asset_library::AssetBlob};
+
+use super::{zip_directory, AssetSource, SourceError};
+
+/// A directory or zip archive on the local filesystem, relative to the project root.
+pub struct PathSource<'a> {
+    pub path: &'a str,
+}
+
+impl AssetSource for PathSource<'_> {
+    async fn fetch(&self) -> Result<AssetBlob, SourceError> {
+        let path = Path::new(self.path);
+        if path.is_dir() {
+            return zip_directory(path);
+        }
+
+        let bytes = crate::fs::read(path)?;
+        Ok(AssetBlob {
+            sha256: checksum::sha256(&bytes),
+            bytes,
+            version: None,
+        })
+    }
+}
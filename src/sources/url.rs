@@ -0,0 +1,14 @@
+use crate::godot::asset_library::{self, AssetBlob};
+
+use super::{AssetSource, SourceError};
+
+/// A zip archive served over plain HTTP(S).
+pub struct UrlSource<'a> {
+    pub url: &'a str,
+}
+
+impl AssetSource for UrlSource<'_> {
+    async fn fetch(&self) -> Result<AssetBlob, SourceError> {
+        Ok(asset_library::download(self.url).await?)
+    }
+}
@@ -0,0 +1,134 @@
+//! Fixtures shared by the unit tests
+
+use std::{
+    collections::BTreeSet,
+    io::{BufRead, BufReader, Cursor, Write},
+    net::TcpListener,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard, PoisonError},
+    thread,
+};
+
+use semver::Version;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    assets::{self, cache::AssetArchive, checksum, AssetInfo, Overwrite},
+    config::Config,
+    godot::asset_library::AssetBlob,
+};
+
+pub type TestResult<T = ()> = Result<T, Box<dyn std::error::Error>>;
+
+/// Held by tests that depend on the working directory, which the whole test process shares.
+pub static WORKING_DIRECTORY: Mutex<()> = Mutex::new(());
+
+/// Starts a zip archive holding `files`, given as archive paths and contents.
+pub fn zip_writer(files: &[(&str, &str)]) -> TestResult<ZipWriter<Cursor<Vec<u8>>>> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (file_name, contents) in files {
+        writer.start_file(*file_name, SimpleFileOptions::default())?;
+        writer.write_all(contents.as_bytes())?;
+    }
+    Ok(writer)
+}
+
+/// Finishes the zip archive, as if it was downloaded.
+pub fn blob(writer: ZipWriter<Cursor<Vec<u8>>>) -> TestResult<AssetBlob> {
+    let bytes = writer.finish()?.into_inner();
+    Ok(AssetBlob {
+        sha256: checksum::sha256(&bytes),
+        bytes,
+        version: None,
+    })
+}
+
+/// Returns the archive of asset `1234`, holding `files`.
+pub fn archive(files: &[(&str, &str)]) -> TestResult<AssetArchive> {
+    Ok(AssetArchive::from_blob("1234", &blob(zip_writer(files)?)?)?)
+}
+
+/// Moves the test into an empty project in a temporary directory, moving back and removing
+/// the project when dropped, even if the test panics.
+pub struct TestProject {
+    previous_dir: PathBuf,
+    project_dir: PathBuf,
+    _working_directory: MutexGuard<'static, ()>,
+}
+
+impl TestProject {
+    pub fn new() -> TestResult<Self> {
+        let working_directory = WORKING_DIRECTORY
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let project = TestProject {
+            previous_dir: std::env::current_dir()?,
+            project_dir: std::env::temp_dir().join(format!("godam-test-{}", std::process::id())),
+            _working_directory: working_directory,
+        };
+        std::fs::create_dir_all(project.project_dir.join("addons"))?;
+        std::env::set_current_dir(&project.project_dir)?;
+
+        Ok(project)
+    }
+
+    /// Installs asset `1234`, holding `files`, and records it in `godam.toml` like
+    /// `godam install` does.
+    pub fn install(&self, files: &[(&str, &str)]) -> TestResult {
+        std::fs::write("project.godot", "config_version=5\n")?;
+        Config::init(&Some(Version::new(4, 3, 0)))?;
+
+        let asset: AssetInfo = toml::from_str("title = \"Tool\"")?;
+        let install_folders =
+            assets::install(archive(files)?, &asset, &BTreeSet::new(), Overwrite::Owned)?;
+        let mut config = Config::get()?;
+        config.add_asset("1234".to_string(), asset)?;
+        config.set_install_folders("1234", install_folders)?;
+        Ok(())
+    }
+}
+
+impl Drop for TestProject {
+    fn drop(&mut self) {
+        let _ = std::env::set_current_dir(&self.previous_dir);
+        let _ = std::fs::remove_dir_all(&self.project_dir);
+    }
+}
+
+/// Serves every request whose path starts with one of the routes with its body, and anything
+/// else with a 404, returning the server's URL. The routes are built from that URL.
+pub fn serve(
+    routes: impl FnOnce(&str) -> TestResult<Vec<(&'static str, Vec<u8>)>>,
+) -> TestResult<String> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}", listener.local_addr()?);
+    let routes = routes(&url)?;
+
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(&stream);
+            let mut request_line = String::new();
+            let mut header = String::new();
+            if reader.read_line(&mut request_line).is_err() {
+                continue;
+            }
+            while reader.read_line(&mut header).is_ok_and(|read| read > 2) {
+                header.clear();
+            }
+
+            let path = request_line.split(' ').nth(1).unwrap_or_default();
+            let (status, body) = match routes.iter().find(|(route, _)| path.starts_with(route)) {
+                Some((_, body)) => ("200 OK", body.as_slice()),
+                None => ("404 Not Found", [].as_slice()),
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = stream.write_all(body);
+        }
+    });
+
+    Ok(url)
+}